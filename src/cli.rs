@@ -79,6 +79,22 @@ pub enum Commands {
         /// Files to check
         files: Vec<PathBuf>,
     },
+
+    /// Report broken relative links and #anchors in markdown file(s)
+    CheckLinks {
+        /// Files to check
+        files: Vec<PathBuf>,
+    },
+
+    /// Print statistics about markdown file(s)
+    Stats {
+        /// Files to analyze
+        files: Vec<PathBuf>,
+
+        /// Report done/total task list items per file
+        #[arg(long)]
+        tasks: bool,
+    },
 }
 
 impl Cli {
@@ -99,9 +115,24 @@ mod tests {
         assert!(!cli.in_place);
     }
 
+    #[test]
+    fn test_cli_stats_tasks() {
+        let cli = Cli::parse_from(["beautiful-md", "stats", "--tasks", "board.md"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Stats { tasks: true, .. })
+        ));
+    }
+
     #[test]
     fn test_cli_in_place() {
         let cli = Cli::parse_from(["beautiful-md", "--in-place", "test.md"]);
         assert!(cli.in_place);
     }
+
+    #[test]
+    fn test_cli_check_links() {
+        let cli = Cli::parse_from(["beautiful-md", "check-links", "README.md"]);
+        assert!(matches!(cli.command, Some(Commands::CheckLinks { .. })));
+    }
 }
@@ -37,6 +37,21 @@ pub struct Config {
 
     /// Code block formatting options.
     pub code: CodeConfig,
+
+    /// Table of contents options.
+    pub toc: TocConfig,
+
+    /// Emphasis marker options.
+    pub emphasis: EmphasisConfig,
+
+    /// Link formatting options.
+    pub links: LinksConfig,
+
+    /// Image options.
+    pub images: ImageConfig,
+
+    /// Paragraph text options.
+    pub paragraphs: ParagraphConfig,
 }
 
 /// Configuration for table formatting.
@@ -51,6 +66,28 @@ pub struct TableConfig {
 
     /// Padding around cell content.
     pub padding: usize,
+
+    /// Convert simple HTML `<table>` blocks into Markdown tables.
+    pub convert_html: bool,
+
+    /// How to repair rows with the wrong number of cells.
+    pub ragged_rows: RaggedRows,
+}
+
+/// Repair strategy for table rows with the wrong number of cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RaggedRows {
+    /// Widen the table to its longest row and pad shorter rows with empty cells.
+    #[default]
+    Pad,
+
+    /// Keep the header's column count: pad short rows and merge extra cells
+    /// into the last column.
+    Merge,
+
+    /// Leave rows as they are and only report the mismatch.
+    Preserve,
 }
 
 /// Configuration for heading formatting.
@@ -63,8 +100,194 @@ pub struct HeadingConfig {
     /// Number of blank lines after headings.
     pub blank_lines_after: usize,
 
+    /// Number of blank lines before a heading that opens the document
+    /// (or directly follows front matter).
+    pub blank_lines_at_start: usize,
+
+    /// Number of blank lines between a heading and a heading directly after
+    /// it. Defaults to the second heading's `blank_lines_before`.
+    pub blank_lines_between: Option<usize>,
+
+    /// Per-level overrides of the blank lines around headings.
+    pub levels: HeadingLevels,
+
     /// Ensure space after `#` symbols.
     pub space_after_hash: bool,
+
+    /// Heading syntax to use.
+    pub style: HeadingStyle,
+
+    /// Closing `#` sequence on ATX headings (`## Title ##`).
+    pub closing_hashes: ClosingHashes,
+
+    /// Automatic section numbering.
+    pub numbering: NumberingConfig,
+
+    /// Capitalization of heading text.
+    pub case: HeadingCase,
+
+    /// Words whose spelling is kept as listed when changing heading case
+    /// (product names, acronyms).
+    pub protected_words: Vec<String>,
+
+    /// Shift heading levels so the outline never skips a level.
+    pub fix_increments: bool,
+
+    /// How explicit `{#id}` attributes are handled.
+    pub ids: HeadingIds,
+
+    /// Algorithm used to derive anchors from heading text.
+    pub slug_style: SlugStyle,
+}
+
+/// Handling of explicit heading IDs (`## Install {#install}`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingIds {
+    /// Keep existing IDs and add none.
+    #[default]
+    Preserve,
+
+    /// Give every heading without an ID one derived from its text.
+    Add,
+
+    /// Strip all heading IDs.
+    Remove,
+}
+
+/// Algorithm for turning heading text into an anchor slug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SlugStyle {
+    /// GitHub: punctuation dropped, each space becomes a hyphen.
+    #[default]
+    Github,
+
+    /// GitLab: like GitHub, but runs of hyphens are collapsed.
+    Gitlab,
+
+    /// `MkDocs`: ASCII only, runs of spaces and hyphens become one hyphen.
+    Mkdocs,
+}
+
+/// Capitalization style for heading text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingCase {
+    /// Leave heading text as written.
+    #[default]
+    Preserve,
+
+    /// Capitalize only the first word (`Getting started`).
+    Sentence,
+
+    /// Capitalize every word except minor ones (`Getting Started with the CLI`).
+    Title,
+}
+
+/// Blank-line overrides for each heading level (`[headings.levels.h2]`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeadingLevels {
+    /// Overrides for level 1 headings.
+    pub h1: LevelSpacing,
+
+    /// Overrides for level 2 headings.
+    pub h2: LevelSpacing,
+
+    /// Overrides for level 3 headings.
+    pub h3: LevelSpacing,
+
+    /// Overrides for level 4 headings.
+    pub h4: LevelSpacing,
+
+    /// Overrides for level 5 headings.
+    pub h5: LevelSpacing,
+
+    /// Overrides for level 6 headings.
+    pub h6: LevelSpacing,
+}
+
+/// Blank lines around headings of one level; unset values use the defaults.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelSpacing {
+    /// Number of blank lines before headings of this level.
+    pub blank_lines_before: Option<usize>,
+
+    /// Number of blank lines after headings of this level.
+    pub blank_lines_after: Option<usize>,
+}
+
+/// Configuration for automatic section numbering (`1.`, `1.2.`, `1.2.3.`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberingConfig {
+    /// Whether to add, remove or leave section numbers alone.
+    ///
+    /// Turning numbering off means `remove`; `preserve` keeps any numbers
+    /// already in the headings.
+    pub mode: NumberingMode,
+
+    /// Heading level that gets the first number component.
+    pub start_level: usize,
+
+    /// Deepest heading level to number.
+    ///
+    /// An absolute level like `toc.max_depth`, not a count from
+    /// `start_level`: with `start_level = 2` and `max_depth = 3`, H2 and H3
+    /// are numbered.
+    pub max_depth: usize,
+
+    /// Number template; `{number}` is replaced by the dotted section number.
+    pub format: String,
+}
+
+/// Section numbering mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NumberingMode {
+    /// Leave heading text as written, including existing numbers.
+    #[default]
+    Preserve,
+
+    /// Insert or renumber section numbers.
+    Add,
+
+    /// Strip section numbers in the configured format, for turning
+    /// numbering off.
+    Remove,
+}
+
+/// Heading syntax style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadingStyle {
+    /// ATX headings (`# Title`).
+    #[default]
+    Atx,
+
+    /// Setext headings (`Title` underlined with `=` or `-`) for levels 1 and 2,
+    /// ATX for deeper levels.
+    Setext,
+
+    /// Keep each heading's original style.
+    Preserve,
+}
+
+/// Handling of closing `#` sequences on ATX headings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClosingHashes {
+    /// Strip closing hashes (`## Title`).
+    #[default]
+    Remove,
+
+    /// Close every heading with as many hashes as it opens with (`## Title ##`).
+    Add,
+
+    /// Keep closing hashes only on headings that already have them.
+    Preserve,
 }
 
 /// Configuration for list formatting.
@@ -74,11 +297,119 @@ pub struct ListConfig {
     /// Indentation size in spaces.
     pub indent_size: usize,
 
-    /// Bullet marker character (`-`, `*`, or `+`).
-    pub marker: String,
+    /// Bullet marker (`-`, `*`, or `+`), markers cycling by nesting depth,
+    /// or `"consistent"`.
+    pub marker: BulletMarker,
 
-    /// Normalize ordered list numbers (1, 2, 3... vs 1, 1, 1...).
+    /// Normalize ordered list numbers; `false` keeps them as written.
     pub normalize_numbers: bool,
+
+    /// Numbering of ordered list items.
+    pub ordered_style: OrderedStyle,
+
+    /// Delimiter after ordered list numbers.
+    pub ordered_delimiter: OrderedDelimiter,
+
+    /// Right-align ordered list numbers of different widths (` 9.`, `10.`).
+    pub align_numbers: bool,
+
+    /// Reorder task list items by their checkbox state.
+    pub sort_tasks: SortTasks,
+
+    /// Blank lines between list items.
+    pub spacing: ListSpacing,
+
+    /// How far nested lists are indented from their parent item.
+    pub indent_style: IndentStyle,
+}
+
+/// Bullet marker used for unordered lists.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BulletMarker {
+    /// One marker for every level (`"-"`), or `"consistent"` to keep the
+    /// marker the first item of each list uses.
+    Single(String),
+
+    /// Markers cycling by nesting depth (`["-", "*", "+"]`).
+    PerLevel(Vec<String>),
+}
+
+/// Numbering of ordered list items.
+///
+/// Lists always keep their start number (`5.` stays `5.`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrderedStyle {
+    /// Count up from the start number (`1.`, `2.`, `3.`).
+    #[default]
+    Sequential,
+
+    /// Repeat the start number on every item (`1.`, `1.`, `1.`).
+    One,
+
+    /// Keep the numbers as written.
+    Preserve,
+}
+
+/// Delimiter after ordered list numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrderedDelimiter {
+    /// `1.`
+    #[default]
+    #[serde(rename = ".")]
+    Period,
+
+    /// `1)`
+    #[serde(rename = ")")]
+    Paren,
+
+    /// Keep each list's delimiter.
+    Preserve,
+}
+
+/// Ordering of task list items (`- [ ]`, `- [x]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortTasks {
+    /// Keep items in their written order.
+    #[default]
+    None,
+
+    /// Move unchecked tasks to the top of their list.
+    UncheckedFirst,
+
+    /// Move checked tasks to the bottom of their list.
+    CheckedLast,
+}
+
+/// Blank lines between list items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ListSpacing {
+    /// Keep each list tight or loose as written.
+    #[default]
+    Preserve,
+
+    /// No blank lines between items.
+    Tight,
+
+    /// A blank line between every item.
+    Loose,
+}
+
+/// Indentation of nested lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndentStyle {
+    /// Indent by `indent_size`, widened where a parent's marker needs more room.
+    #[default]
+    Fixed,
+
+    /// Indent lists nested in ordered items to the parent's content column
+    /// (3 spaces under `1.`, 4 under `10.`).
+    MarkerWidth,
 }
 
 /// Configuration for code block formatting.
@@ -92,12 +423,209 @@ pub struct CodeConfig {
     pub fence_style: String,
 }
 
+/// Configuration for the generated table of contents.
+///
+/// The TOC list itself follows the `[lists]` marker and indentation settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TocConfig {
+    /// Shallowest heading level to include.
+    pub min_depth: usize,
+
+    /// Deepest heading level to include.
+    pub max_depth: usize,
+
+    /// Leave out the heading of the section containing the TOC.
+    pub exclude_heading: bool,
+}
+
+/// Configuration for emphasis and strong emphasis markers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmphasisConfig {
+    /// Marker for emphasis (`*` or `_`).
+    pub em: EmphasisMarker,
+
+    /// Marker for strong emphasis (`**` or `__`).
+    pub strong: StrongMarker,
+}
+
+/// Marker written around emphasized text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EmphasisMarker {
+    /// `*italic*`.
+    #[default]
+    #[serde(rename = "*")]
+    Asterisk,
+    /// `_italic_`, falling back to `*` inside words where `_` does not work.
+    #[serde(rename = "_")]
+    Underscore,
+}
+
+impl EmphasisMarker {
+    /// The marker character.
+    #[must_use]
+    pub const fn token(self) -> char {
+        match self {
+            Self::Asterisk => '*',
+            Self::Underscore => '_',
+        }
+    }
+}
+
+/// Marker written around strongly emphasized text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StrongMarker {
+    /// `**bold**`.
+    #[default]
+    #[serde(rename = "**")]
+    Asterisk,
+    /// `__bold__`, falling back to `**` inside words where `__` does not work.
+    #[serde(rename = "__")]
+    Underscore,
+}
+
+impl StrongMarker {
+    /// The marker string.
+    #[must_use]
+    pub const fn token(self) -> &'static str {
+        match self {
+            Self::Asterisk => "**",
+            Self::Underscore => "__",
+        }
+    }
+}
+
+/// Configuration for paragraph text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParagraphConfig {
+    /// How paragraph text is broken into lines.
+    pub wrap: WrapMode,
+
+    /// Maximum line width for `wrap = "always"`, including indentation.
+    pub line_width: usize,
+}
+
+/// Line breaking of paragraph text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WrapMode {
+    /// Keep line breaks as written.
+    #[default]
+    Preserve,
+    /// Reflow text to fill lines up to `line_width`.
+    Always,
+    /// Join each paragraph into a single line.
+    Never,
+    /// Put each sentence on its own line (semantic line breaks).
+    Sentence,
+}
+
+/// Configuration for images.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    /// Convert `<img>` tags with only `src`, `alt` and `title` to `![alt](src)`.
+    pub convert_html: bool,
+}
+
+/// Configuration for link formatting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LinksConfig {
+    /// Whether links are written inline or as references.
+    pub style: LinkStyle,
+
+    /// How labels are chosen for links converted to references.
+    pub reference_labels: ReferenceLabels,
+
+    /// Where reference definitions are written.
+    pub reference_placement: ReferencePlacement,
+
+    /// Sort reference definitions by label.
+    pub sort_definitions: bool,
+
+    /// Case of reference labels.
+    pub label_case: LabelCase,
+
+    /// Drop reference definitions that no link uses.
+    pub remove_unused: bool,
+
+    /// How bare URLs and `<url>` autolinks are written.
+    pub autolink: AutolinkStyle,
+
+    /// Report broken relative links and anchors when formatting files.
+    pub check_local: bool,
+}
+
+/// Link syntax to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkStyle {
+    /// Keep each link as written.
+    #[default]
+    Preserve,
+    /// `[text](url)`: reference links are inlined.
+    Inline,
+    /// `[text][label]`: inline links get a reference definition.
+    Reference,
+}
+
+/// Label scheme for links converted to references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceLabels {
+    /// `[text][1]`, numbered in order of first use.
+    #[default]
+    Numbered,
+    /// `[Some text][some-text]`, derived from the link text.
+    Slug,
+}
+
+/// Placement of reference definitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferencePlacement {
+    /// All definitions at the end of the document.
+    #[default]
+    Document,
+    /// Definitions at the end of the section where they are first used.
+    Section,
+}
+
+/// Syntax for URLs written as their own link text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutolinkStyle {
+    /// Keep bare URLs and autolinks as written.
+    #[default]
+    Preserve,
+    /// Wrap bare URLs in angle brackets: `<https://example.com>`.
+    Angle,
+    /// Drop angle brackets where the bare URL is still recognized.
+    Bare,
+}
+
+/// Case of reference labels in definitions and `[text][label]` links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LabelCase {
+    /// Keep labels as written.
+    #[default]
+    Preserve,
+    /// Lowercase labels.
+    Lower,
+}
+
 impl Default for TableConfig {
     fn default() -> Self {
         Self {
             align: true,
             min_column_width: 3,
             padding: 1,
+            convert_html: false,
+            ragged_rows: RaggedRows::Pad,
         }
     }
 }
@@ -107,7 +635,70 @@ impl Default for HeadingConfig {
         Self {
             blank_lines_before: 1,
             blank_lines_after: 1,
+            blank_lines_at_start: 0,
+            blank_lines_between: None,
+            levels: HeadingLevels::default(),
             space_after_hash: true,
+            style: HeadingStyle::Atx,
+            closing_hashes: ClosingHashes::Remove,
+            numbering: NumberingConfig::default(),
+            case: HeadingCase::Preserve,
+            protected_words: Vec::new(),
+            fix_increments: false,
+            ids: HeadingIds::Preserve,
+            slug_style: SlugStyle::Github,
+        }
+    }
+}
+
+impl HeadingConfig {
+    /// Blank lines before and after a heading of the given level.
+    #[must_use]
+    pub fn spacing(&self, level: usize) -> (usize, usize) {
+        let overrides = match level {
+            1 => self.levels.h1,
+            2 => self.levels.h2,
+            3 => self.levels.h3,
+            4 => self.levels.h4,
+            5 => self.levels.h5,
+            _ => self.levels.h6,
+        };
+        (
+            overrides
+                .blank_lines_before
+                .unwrap_or(self.blank_lines_before),
+            overrides
+                .blank_lines_after
+                .unwrap_or(self.blank_lines_after),
+        )
+    }
+}
+
+impl BulletMarker {
+    /// Marker value that keeps each list's own marker.
+    pub const CONSISTENT: &'static str = "consistent";
+
+    /// Marker for an item at `depth`, given its list's original marker.
+    #[must_use]
+    pub fn for_depth(&self, depth: usize, original: Option<char>) -> String {
+        match self {
+            Self::Single(marker) if marker == Self::CONSISTENT => {
+                original.map_or_else(|| String::from("-"), String::from)
+            }
+            Self::Single(marker) => marker.clone(),
+            Self::PerLevel(markers) if markers.is_empty() => String::from("-"),
+            Self::PerLevel(markers) => markers[depth % markers.len()].clone(),
+        }
+    }
+}
+
+impl Default for NumberingConfig {
+    fn default() -> Self {
+        Self {
+            mode: NumberingMode::Preserve,
+            start_level: 1,
+            max_depth: 6,
+            format: String::from("{number}."),
         }
     }
 }
@@ -116,8 +707,14 @@ impl Default for ListConfig {
     fn default() -> Self {
         Self {
             indent_size: 2,
-            marker: String::from("-"),
+            marker: BulletMarker::Single(String::from("-")),
             normalize_numbers: true,
+            ordered_style: OrderedStyle::Sequential,
+            ordered_delimiter: OrderedDelimiter::Period,
+            align_numbers: false,
+            sort_tasks: SortTasks::None,
+            spacing: ListSpacing::Preserve,
+            indent_style: IndentStyle::Fixed,
         }
     }
 }
@@ -131,18 +728,75 @@ impl Default for CodeConfig {
     }
 }
 
+impl Default for TocConfig {
+    fn default() -> Self {
+        Self {
+            min_depth: 1,
+            max_depth: 6,
+            exclude_heading: true,
+        }
+    }
+}
+
+impl Default for ParagraphConfig {
+    fn default() -> Self {
+        Self {
+            wrap: WrapMode::Preserve,
+            line_width: 80,
+        }
+    }
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            style: LinkStyle::Preserve,
+            reference_labels: ReferenceLabels::Numbered,
+            reference_placement: ReferencePlacement::Document,
+            sort_definitions: false,
+            label_case: LabelCase::Preserve,
+            remove_unused: false,
+            autolink: AutolinkStyle::Preserve,
+            check_local: false,
+        }
+    }
+}
+
+impl Default for EmphasisConfig {
+    fn default() -> Self {
+        Self {
+            em: EmphasisMarker::Asterisk,
+            strong: StrongMarker::Asterisk,
+        }
+    }
+}
+
 impl Config {
     /// Load configuration from a TOML file.
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read or parsed.
+    /// Returns an error if the file cannot be read or parsed, or a value is
+    /// out of range.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = std::fs::read_to_string(path.as_ref()).map_err(Error::Io)?;
         let config: Self = toml::from_str(&content)?;
+        config.validate()?;
         Ok(config)
     }
 
+    /// Reject values that deserialize but cannot be used.
+    fn validate(&self) -> Result<()> {
+        let numbering = &self.headings.numbering;
+        if !(1..=6).contains(&numbering.start_level) {
+            return Err(Error::ConfigError(format!(
+                "headings.numbering.start_level must be between 1 and 6, got {}",
+                numbering.start_level
+            )));
+        }
+        Ok(())
+    }
+
     /// Try to load configuration from default locations.
     ///
     /// Searches for `.beautiful-md.toml` in:
@@ -192,7 +846,7 @@ mod tests {
         assert!(config.tables.align);
         assert_eq!(config.tables.min_column_width, 3);
         assert_eq!(config.headings.blank_lines_before, 1);
-        assert_eq!(config.lists.marker, "-");
+        assert_eq!(config.lists.marker, BulletMarker::Single(String::from("-")));
     }
 
     #[test]
@@ -202,4 +856,21 @@ mod tests {
         let parsed: Config = toml::from_str(&toml).unwrap();
         assert_eq!(parsed.tables.align, config.tables.align);
     }
+
+    #[test]
+    fn test_bullet_marker_array() {
+        let config: Config = toml::from_str("[lists]\nmarker = [\"-\", \"*\", \"+\"]").unwrap();
+        assert_eq!(config.lists.marker.for_depth(0, None), "-");
+        assert_eq!(config.lists.marker.for_depth(4, None), "*");
+
+        let config: Config = toml::from_str("[lists]\nmarker = \"consistent\"").unwrap();
+        assert_eq!(config.lists.marker.for_depth(1, Some('+')), "+");
+    }
+
+    #[test]
+    fn test_validate_numbering_start_level() {
+        let config: Config = toml::from_str("[headings.numbering]\nstart_level = 0").unwrap();
+        assert!(config.validate().is_err());
+        assert!(Config::default().validate().is_ok());
+    }
 }
@@ -21,6 +21,16 @@ pub enum DiagnosticKind {
     MalformedTable,
     /// Code block without proper closing fence
     UnclosedCodeBlock,
+    /// HTML table that was converted to, or could not be converted to, Markdown
+    HtmlTable,
+    /// Heading text or structure issue
+    Heading,
+    /// List structure issue
+    List,
+    /// Link or reference definition issue
+    Link,
+    /// Image issue, such as missing alt text
+    Image,
     /// Other markdown issues
     Other,
 }
@@ -34,6 +44,8 @@ pub struct Diagnostic {
     pub kind: DiagnosticKind,
     /// Line number where issue was found (1-indexed)
     pub line: usize,
+    /// Optional column where issue was found (1-indexed)
+    pub column: Option<usize>,
     /// Human-readable message
     pub message: String,
     /// Optional snippet of the problematic line
@@ -52,11 +64,27 @@ impl Diagnostic {
             severity,
             kind,
             line,
+            column: None,
             message: message.into(),
             snippet: None,
         }
     }
 
+    /// Add a column to the diagnostic.
+    #[must_use]
+    pub const fn with_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    /// Location as `Line 3` or `Line 3, column 7`.
+    fn location(&self) -> String {
+        self.column.map_or_else(
+            || format!("Line {}", self.line),
+            |column| format!("Line {}, column {column}", self.line),
+        )
+    }
+
     /// Add a code snippet to the diagnostic.
     #[must_use]
     pub fn with_snippet(mut self, snippet: impl Into<String>) -> Self {
@@ -72,7 +100,7 @@ impl fmt::Display for Diagnostic {
             Severity::Info => "ℹ️",
         };
 
-        write!(f, "{severity_icon} Line {}: {}", self.line, self.message)?;
+        write!(f, "{severity_icon} {}: {}", self.location(), self.message)?;
 
         if let Some(snippet) = &self.snippet {
             write!(f, "\n  │ {snippet}")?;
@@ -112,6 +140,29 @@ impl Diagnostics {
         self.add(Diagnostic::new(Severity::Info, kind, line, message));
     }
 
+    /// Add all diagnostics from `other`.
+    pub fn extend(&mut self, other: Self) {
+        self.messages.extend(other.messages);
+    }
+
+    /// Shift every line number down by `offset` lines.
+    ///
+    /// Used when diagnostics were collected on part of a document.
+    pub fn offset_lines(&mut self, offset: usize) {
+        for diagnostic in &mut self.messages {
+            diagnostic.line += offset;
+        }
+    }
+
+    /// Replace every line number with `map(line)`.
+    ///
+    /// Used when diagnostics were collected on rewritten content.
+    pub fn map_lines(&mut self, map: impl Fn(usize) -> usize) {
+        for diagnostic in &mut self.messages {
+            diagnostic.line = map(diagnostic.line);
+        }
+    }
+
     /// Check if there are any diagnostics.
     #[must_use]
     pub fn is_empty(&self) -> bool {
@@ -183,7 +234,7 @@ impl Diagnostics {
             Severity::Info => "ℹ️",
         };
 
-        let line_text = format!("Line {}", diagnostic.line);
+        let line_text = diagnostic.location();
         eprint!(
             "{} {}: ",
             severity_icon,
@@ -3,6 +3,8 @@
 //! This module coordinates the various formatters to produce beautifully
 //! formatted markdown.
 
+use std::ops::Range;
+
 use crate::config::Config;
 use crate::diagnostics::Diagnostics;
 use crate::error::{Error, Result};
@@ -18,27 +20,59 @@ use crate::preprocessor;
 ///
 /// Returns an error if parsing or formatting fails.
 pub fn format(content: &str, config: &Config) -> Result<(String, Diagnostics)> {
+    // Front matter is kept verbatim and never formatted
+    let (front_matter, content) = split_front_matter(content);
+
     // Extract code blocks FIRST to preserve them completely verbatim
     let (protected_content, code_blocks) = formatters::extract_code_blocks_early(content);
 
     // Pre-process to fix common issues and collect diagnostics (without code blocks)
-    let (preprocessed, diagnostics) = preprocessor::preprocess(&protected_content);
+    let (preprocessed, mut diagnostics) = preprocessor::preprocess(&protected_content, config);
 
     // Parse markdown (without code blocks)
     let events = parse_markdown(&preprocessed);
 
     // Apply formatters in order
-    let formatted = apply_formatters(&events, config)?;
+    let formatted = apply_formatters(events, &preprocessed, config, &mut diagnostics)?;
 
     // Restore code blocks with original content preserved
     let final_content = formatters::restore_code_blocks_early(&formatted, &code_blocks, config);
+    diagnostics.map_lines(|line| formatters::original_line(&preprocessed, &code_blocks, line));
+
+    match front_matter {
+        Some(front_matter) => {
+            diagnostics.offset_lines(front_matter.lines().count());
+            Ok((format!("{front_matter}\n{final_content}"), diagnostics))
+        }
+        None => Ok((final_content, diagnostics)),
+    }
+}
 
-    Ok((final_content, diagnostics))
+/// Split YAML (`---`) or TOML (`+++`) front matter from the start of a document.
+///
+/// Returns the front matter including its fences, and the remaining body.
+pub fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let Some(first_line) = content.lines().next() else {
+        return (None, content);
+    };
+    let fence = first_line.trim_end();
+    if fence != "---" && fence != "+++" {
+        return (None, content);
+    }
+
+    let mut offset = content.find('\n').map_or(content.len(), |pos| pos + 1);
+    for line in content[offset..].split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == fence {
+            return (Some(content[..offset].trim_end()), &content[offset..]);
+        }
+    }
+    (None, content)
 }
 
-/// Parse markdown content into events.
-fn parse_markdown(content: &str) -> Vec<pulldown_cmark::Event<'_>> {
-    use pulldown_cmark::{Options, Parser};
+/// Markdown extensions enabled when parsing.
+pub fn parser_options() -> pulldown_cmark::Options {
+    use pulldown_cmark::Options;
 
     let mut options = Options::empty();
     options.insert(Options::ENABLE_TABLES);
@@ -46,23 +80,38 @@ fn parse_markdown(content: &str) -> Vec<pulldown_cmark::Event<'_>> {
     options.insert(Options::ENABLE_STRIKETHROUGH);
     options.insert(Options::ENABLE_TASKLISTS);
     options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
+    options
+}
 
-    let parser = Parser::new_ext(content, options);
-    parser.collect()
+/// Parse markdown content into events with their source ranges.
+pub fn parse_markdown(content: &str) -> Vec<(pulldown_cmark::Event<'_>, Range<usize>)> {
+    let parser = pulldown_cmark::Parser::new_ext(content, parser_options());
+    parser.into_offset_iter().collect()
 }
 
 /// Apply all formatters to the parsed markdown.
-fn apply_formatters(events: &[pulldown_cmark::Event<'_>], config: &Config) -> Result<String> {
-    use pulldown_cmark_to_cmark::cmark;
-
-    // For now, we'll use the basic cmark formatter
-    // Individual formatters will be implemented next
+fn apply_formatters(
+    events: Vec<(pulldown_cmark::Event<'_>, Range<usize>)>,
+    source: &str,
+    config: &Config,
+    diagnostics: &mut Diagnostics,
+) -> Result<String> {
+    use pulldown_cmark_to_cmark::{cmark_with_options, Options};
+
+    // Apply event-level formatters before serializing
+    let (events, info) = formatters::transform_events(events, source, config, diagnostics);
+
+    let options = Options {
+        emphasis_token: config.emphasis.em.token(),
+        strong_token: config.emphasis.strong.token(),
+        ..Options::default()
+    };
     let mut buf = String::new();
-    cmark(events.iter(), &mut buf)
+    cmark_with_options(events.iter(), &mut buf, options)
         .map_err(|e| Error::FormattingError(format!("Failed to format markdown: {e}")))?;
 
     // Apply post-processing formatters
-    let formatted = formatters::apply_all(&buf, config);
+    let formatted = formatters::apply_all(&buf, config, &info);
 
     Ok(formatted)
 }
@@ -71,6 +120,16 @@ fn apply_formatters(events: &[pulldown_cmark::Event<'_>], config: &Config) -> Re
 mod tests {
     use super::*;
 
+    /// Render Markdown to HTML, ignoring whitespace differences.
+    fn render(markdown: &str) -> String {
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(
+            &mut html,
+            pulldown_cmark::Parser::new_ext(markdown, parser_options()),
+        );
+        html.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
     #[test]
     fn test_basic_formatting() {
         let input = "# Hello\n\nWorld";
@@ -79,6 +138,85 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_front_matter() {
+        let input = "---\ntitle: x\n# comment\n---\n\n# Title\n\nText\n";
+        let (result, _) = format(input, &Config::default()).unwrap();
+        assert!(result.starts_with("---\ntitle: x\n# comment\n---\n# Title\n\nText"));
+
+        assert_eq!(split_front_matter("---\nno end"), (None, "---\nno end"));
+        assert_eq!(
+            split_front_matter("+++\na = 1\n+++\nBody"),
+            (Some("+++\na = 1\n+++"), "Body")
+        );
+    }
+
+    #[test]
+    fn test_emphasis_markers() {
+        let mut config = Config::default();
+        config.emphasis.em = crate::config::EmphasisMarker::Underscore;
+        config.emphasis.strong = crate::config::StrongMarker::Underscore;
+        let (result, _) = format("*a* **b** un*c*d", &config).unwrap();
+        assert_eq!(result.trim_end(), "_a_ __b__ un*c*d");
+    }
+
+    #[test]
+    fn test_diagnostic_lines_after_code_blocks() {
+        let input = "---\nx: 1\n---\n# A\n\n```\n1\n2\n```\n\n#### Jump\n\n![](x.png)\n";
+        let (_, diagnostics) = format(input, &Config::default()).unwrap();
+        let lines: Vec<usize> = diagnostics.messages().iter().map(|d| d.line).collect();
+        assert_eq!(lines, [11, 13]);
+    }
+
+    #[test]
+    fn test_setext_numbering_idempotent() {
+        let mut config = Config::default();
+        config.headings.style = crate::config::HeadingStyle::Setext;
+        config.headings.ids = crate::config::HeadingIds::Add;
+        config.headings.numbering.mode = crate::config::NumberingMode::Add;
+        let input = "# Intro\n\nText.\n\n## Scope\n\n## 1.5x Faster\n";
+
+        let (once, _) = format(input, &config).unwrap();
+        assert!(once.starts_with("1\\. Intro {#intro}\n====="));
+        let (twice, _) = format(&once, &config).unwrap();
+        assert_eq!(twice, once);
+    }
+
+    #[test]
+    fn test_sentence_wrap_renders_identically() {
+        let input = "# Title. Kept\n\n\
+                     One sentence. Two, e.g. `code. Here` and [a link. Inside](x.md)! \
+                     Dr. Smith said \"yes.\" Three...\nfour? *Five.* 1. Not a list.\n\n\
+                     - Item one. Item two.\n\n\
+                     > Quoted. Again.\n\n\
+                     First one. <!-- note --> Second one.\n\n\
+                     Alpha here. <div>Beta</div> there.\n\n\
+                     See [\\[rust\\] tag](x). And [PR \\#5] too. Then \\*b\\* here.\n\n\
+                     [foo]: Done. More text here.\n\n\
+                     <x-a/> Next. Sentence here.\n\n\
+                     [PR \\#5]: /p\n[foo]: /f\n";
+        let mut config = Config::default();
+        config.paragraphs.wrap = crate::config::WrapMode::Sentence;
+        let (result, _) = format(input, &config).unwrap();
+        assert!(result.contains("One sentence.\nTwo, e.g."));
+        assert_eq!(render(&result), render(input));
+    }
+
+    #[test]
+    fn test_wrap_renders_identically() {
+        let input = "See [\\[rust\\] tag](x) and [PR \\#5] now, a \\*b\\* c.\n\n\
+                     [foo]: bar baz qux\n\n\
+                     [PR \\#5]: see the notes\n\n\
+                     <x-a/> averyverylongword more words here\n\n\
+                     [PR \\#5]: /p\n";
+        let mut config = Config::default();
+        config.paragraphs.wrap = crate::config::WrapMode::Always;
+        config.paragraphs.line_width = 10;
+        let (result, _) = format(input, &config).unwrap();
+        assert!(result.contains("[\\[rust\\] tag](x)\n"));
+        assert_eq!(render(&result), render(input));
+    }
+
     #[test]
     fn test_parse_markdown() {
         let input = "# Heading\n\nParagraph";
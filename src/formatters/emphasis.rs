@@ -0,0 +1,150 @@
+//! Emphasis formatting module.
+//!
+//! The configured markers are applied when the events are serialized. This
+//! pass handles the places where an underscore marker would not be parsed.
+
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+use crate::config::{EmphasisConfig, EmphasisMarker, StrongMarker};
+
+/// Keep asterisks for emphasis inside words.
+///
+/// `_` and `__` only open and close emphasis at word boundaries, so
+/// `un*frigging*believable` cannot use them. Such spans are written with
+/// literal `*` markers instead of the configured underscore. The same goes
+/// for spans nested right inside such a span (`x***y***z`) and spans right
+/// after another underscore span (`*a*_b_`), whose markers would run together.
+pub fn protect_intraword_emphasis<'a>(
+    mut events: Vec<(Event<'a>, Range<usize>)>,
+    config: &EmphasisConfig,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    if config.em == EmphasisMarker::Asterisk && config.strong == StrongMarker::Asterisk {
+        return events;
+    }
+
+    let mut open = Vec::new();
+    for index in 0..events.len() {
+        match &events[index].0 {
+            Event::Start(Tag::Emphasis | Tag::Strong) => open.push(index),
+            Event::End(end @ (TagEnd::Emphasis | TagEnd::Strong)) => {
+                let Some(start) = open.pop() else {
+                    continue;
+                };
+                let (underscore, fallback) = if *end == TagEnd::Emphasis {
+                    (config.em == EmphasisMarker::Underscore, "*")
+                } else {
+                    (config.strong == StrongMarker::Underscore, "**")
+                };
+                if underscore
+                    && (joins_before(&events[..start], config)
+                        || starts_in_word(&events[index + 1..]))
+                {
+                    events[start].0 = Event::InlineHtml(CowStr::Borrowed(fallback));
+                    events[index].0 = Event::InlineHtml(CowStr::Borrowed(fallback));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Whether an underscore marker after these events would not open.
+///
+/// Looks past the starts of enclosing spans: the text there must not end
+/// with a word character, and no other underscore span may end right there.
+fn joins_before(events: &[(Event<'_>, Range<usize>)], config: &EmphasisConfig) -> bool {
+    for (event, _) in events.iter().rev() {
+        match event {
+            Event::Start(Tag::Emphasis | Tag::Strong) => {}
+            Event::Text(text) => return text.chars().last().is_some_and(char::is_alphanumeric),
+            Event::End(TagEnd::Emphasis) => return config.em == EmphasisMarker::Underscore,
+            Event::End(TagEnd::Strong) => return config.strong == StrongMarker::Underscore,
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// Whether the text after these events, past the ends of enclosing spans,
+/// starts with a word character.
+///
+/// A span starting right after is checked when that span ends.
+fn starts_in_word(events: &[(Event<'_>, Range<usize>)]) -> bool {
+    for (event, _) in events {
+        match event {
+            Event::End(TagEnd::Emphasis | TagEnd::Strong) => {}
+            Event::Text(text) => return text.chars().next().is_some_and(char::is_alphanumeric),
+            _ => return false,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::parse_markdown;
+
+    fn markers(content: &str, config: &EmphasisConfig) -> Vec<String> {
+        protect_intraword_emphasis(parse_markdown(content), config)
+            .into_iter()
+            .filter_map(|(event, _)| match event {
+                Event::InlineHtml(html) => Some(html.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_protect_intraword_emphasis() {
+        let config = EmphasisConfig {
+            em: EmphasisMarker::Underscore,
+            strong: StrongMarker::Underscore,
+        };
+
+        assert!(markers("Some *word* and **more**.", &config).is_empty());
+        assert_eq!(markers("un*frigging*believable", &config), ["*", "*"]);
+        assert_eq!(markers("**foo**bar", &config), ["**", "**"]);
+        assert!(markers("un*frigging*believable", &EmphasisConfig::default()).is_empty());
+        assert_eq!(markers("x***y***z", &config), ["*", "**", "**", "*"]);
+        assert_eq!(markers("*a*_b_", &config), ["*", "*"]);
+        assert!(markers("***a***", &config).is_empty());
+    }
+
+    #[test]
+    fn test_underscore_markers_render_identically() {
+        let config = crate::Config {
+            emphasis: EmphasisConfig {
+                em: EmphasisMarker::Underscore,
+                strong: StrongMarker::Underscore,
+            },
+            ..Default::default()
+        };
+        let render = |markdown: &str| {
+            let mut html = String::new();
+            pulldown_cmark::html::push_html(
+                &mut html,
+                pulldown_cmark::Parser::new_ext(markdown, crate::formatter::parser_options()),
+            );
+            html
+        };
+
+        for input in [
+            "x***y***z",
+            "***a***",
+            "a ***b*** c",
+            "*a*_b_",
+            "*a***b**",
+            "**a***b*",
+            "*a**b***",
+            "un*frig**gi**ng*believable",
+        ] {
+            let (result, _) = crate::formatter::format(input, &config).unwrap();
+            assert_eq!(render(&result), render(input), "{input} became {result}");
+        }
+    }
+}
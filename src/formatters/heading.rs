@@ -2,65 +2,737 @@
 //!
 //! Handles spacing and normalization of Markdown headings.
 
-use crate::config::HeadingConfig;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag, TagEnd};
+
+use super::line_number;
+use crate::config::{
+    ClosingHashes, HeadingCase, HeadingConfig, HeadingIds, HeadingStyle, NumberingConfig,
+    NumberingMode,
+};
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, Severity};
+use crate::formatter::parse_markdown;
+use crate::slug::Slugger;
+
+/// Words that stay lowercase in title case unless they start the heading.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "from", "in", "into", "nor", "of", "on",
+    "or", "per", "the", "to", "via", "vs", "with",
+];
+
+/// Punctuation that should not end a heading.
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!'];
+
+/// Placeholder for the dotted section number in the numbering format.
+#[allow(clippy::literal_string_with_formatting_args)]
+const NUMBER_PLACEHOLDER: &str = "{number}";
+
+/// Heading details gathered from parser events.
+///
+/// Used to render the matching heading line of the serialized output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadingInfo {
+    /// Heading level (1-6).
+    pub level: usize,
+
+    /// Render as a setext heading instead of ATX.
+    pub setext: bool,
+
+    /// Close the ATX heading with a `#` sequence.
+    pub closing_hashes: bool,
+}
+
+/// Process heading events before serialization.
+///
+/// Flattens line breaks inside headings (ATX headings are single-line) and
+/// resolves the output style of every top-level heading. Returns the events
+/// and the heading details, in document order.
+pub fn process_heading_events<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    config: &HeadingConfig,
+) -> (Vec<(Event<'a>, Range<usize>)>, Vec<HeadingInfo>) {
+    let mut result = Vec::with_capacity(events.len());
+    let mut headings = Vec::new();
+    let mut container_depth = 0usize;
+    let mut in_heading = false;
+
+    for (event, range) in events {
+        match &event {
+            Event::Start(Tag::BlockQuote(_) | Tag::Item | Tag::FootnoteDefinition(_)) => {
+                container_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_) | TagEnd::Item | TagEnd::FootnoteDefinition) => {
+                container_depth = container_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::Heading { level, .. }) => {
+                in_heading = true;
+
+                // Only top-level headings are serialized at the start of a line
+                if container_depth == 0 {
+                    let level = *level as usize;
+                    let source_line = source[range.clone()].lines().next().unwrap_or_default();
+                    let was_setext = !source_line.trim_start().starts_with('#');
+                    let setext = level <= 2
+                        && match config.style {
+                            HeadingStyle::Atx => false,
+                            HeadingStyle::Setext => true,
+                            HeadingStyle::Preserve => was_setext,
+                        };
+                    let closing_hashes = match config.closing_hashes {
+                        ClosingHashes::Remove => false,
+                        ClosingHashes::Add => true,
+                        ClosingHashes::Preserve => !was_setext && has_closing_hashes(source_line),
+                    };
+                    headings.push(HeadingInfo {
+                        level,
+                        setext,
+                        closing_hashes,
+                    });
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => in_heading = false,
+            Event::SoftBreak | Event::HardBreak if in_heading => {
+                result.push((Event::Text(CowStr::Borrowed(" ")), range));
+                continue;
+            }
+            _ => {}
+        }
+
+        result.push((event, range));
+    }
+
+    (result, headings)
+}
+
+/// A heading as seen by the hierarchy checks.
+struct OutlineHeading {
+    line: usize,
+    level: usize,
+    text: String,
+    /// Inside a list item, block quote or footnote.
+    nested: bool,
+}
+
+/// Check the heading outline and optionally repair skipped levels.
+///
+/// Reports skipped levels, multiple H1 headings, trailing punctuation and
+/// duplicate headings under the same parent. With `fix_increments`, levels
+/// are shifted so each heading is at most one level below its parent.
+pub fn check_heading_hierarchy<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    config: &HeadingConfig,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let headings = collect_outline(&events, source);
+    let mut levels = Vec::with_capacity(headings.len());
+
+    // Original and new level of each open ancestor
+    let mut ancestors: Vec<(usize, usize)> = Vec::new();
+    let mut previous_level: Option<usize> = None;
+
+    for heading in &headings {
+        while ancestors
+            .last()
+            .is_some_and(|(level, _)| *level >= heading.level)
+        {
+            ancestors.pop();
+        }
+        let level = match ancestors.last() {
+            Some((_, parent)) if config.fix_increments => heading.level.min(parent + 1),
+            _ => heading.level,
+        };
+
+        if let Some(previous) = previous_level.filter(|previous| heading.level > previous + 1) {
+            if level == heading.level {
+                diagnostics.warn(
+                    DiagnosticKind::Heading,
+                    heading.line,
+                    format!("Heading level jumps from H{previous} to H{}", heading.level),
+                );
+            } else {
+                diagnostics.info(
+                    DiagnosticKind::Heading,
+                    heading.line,
+                    format!("Shifted heading from H{} to H{level}", heading.level),
+                );
+            }
+        }
+
+        previous_level = Some(heading.level);
+        ancestors.push((heading.level, level));
+        levels.push(level);
+    }
+
+    check_outline(&headings, &levels, diagnostics);
+
+    if levels
+        .iter()
+        .zip(&headings)
+        .all(|(level, heading)| *level == heading.level)
+    {
+        return events;
+    }
+
+    // Rewrite the levels of the start and end events
+    let mut levels = levels.into_iter();
+    let mut current = None;
+    events
+        .into_iter()
+        .map(|(event, range)| match event {
+            Event::Start(Tag::Heading {
+                level,
+                id,
+                classes,
+                attrs,
+            }) => {
+                current = levels
+                    .next()
+                    .and_then(|new| HeadingLevel::try_from(new).ok());
+                let level = current.unwrap_or(level);
+                let tag = Tag::Heading {
+                    level,
+                    id,
+                    classes,
+                    attrs,
+                };
+                (Event::Start(tag), range)
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let level = current.take().unwrap_or(level);
+                (Event::End(TagEnd::Heading(level)), range)
+            }
+            event => (event, range),
+        })
+        .collect()
+}
+
+/// Collect the level, line and plain text of every heading.
+fn collect_outline(events: &[(Event<'_>, Range<usize>)], source: &str) -> Vec<OutlineHeading> {
+    let mut headings: Vec<OutlineHeading> = Vec::new();
+    let mut in_heading = false;
+    let mut container_depth = 0usize;
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::BlockQuote(_) | Tag::Item | Tag::FootnoteDefinition(_)) => {
+                container_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_) | TagEnd::Item | TagEnd::FootnoteDefinition) => {
+                container_depth = container_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::Heading { level, .. }) => {
+                in_heading = true;
+                headings.push(OutlineHeading {
+                    line: line_number(source, range.start),
+                    level: *level as usize,
+                    text: String::new(),
+                    nested: container_depth > 0,
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => in_heading = false,
+            Event::Text(text) | Event::Code(text) if in_heading => {
+                if let Some(heading) = headings.last_mut() {
+                    heading.text.push_str(text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    headings
+}
+
+/// Report multiple H1s, trailing punctuation and duplicate sibling headings.
+fn check_outline(headings: &[OutlineHeading], levels: &[usize], diagnostics: &mut Diagnostics) {
+    let mut first_h1: Option<usize> = None;
+    // Level of each open ancestor with the text of its children seen so far
+    let mut parents: Vec<(usize, HashMap<String, usize>)> = vec![(0, HashMap::new())];
+
+    for (heading, &level) in headings.iter().zip(levels) {
+        let text = heading.text.trim();
+
+        // Headings in lists and quotes are not part of the document title
+        if level == 1 && !heading.nested {
+            match first_h1 {
+                Some(first) => diagnostics.warn(
+                    DiagnosticKind::Heading,
+                    heading.line,
+                    format!("Multiple top-level headings (first H1 at line {first})"),
+                ),
+                None => first_h1 = Some(heading.line),
+            }
+        }
+
+        if let Some(punctuation) = text
+            .chars()
+            .last()
+            .filter(|c| TRAILING_PUNCTUATION.contains(c))
+        {
+            diagnostics.add(
+                Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::Heading,
+                    heading.line,
+                    format!("Heading ends with punctuation '{punctuation}'"),
+                )
+                .with_snippet(text),
+            );
+        }
+
+        while parents.last().is_some_and(|(parent, _)| *parent >= level) {
+            parents.pop();
+        }
+        if let Some((_, siblings)) = parents.last_mut() {
+            let key = text.to_lowercase();
+            if let Some(first) = siblings.get(&key) {
+                diagnostics.add(
+                    Diagnostic::new(
+                        Severity::Warning,
+                        DiagnosticKind::Heading,
+                        heading.line,
+                        format!("Duplicate heading under the same parent (first at line {first})"),
+                    )
+                    .with_snippet(text),
+                );
+            } else {
+                siblings.insert(key, heading.line);
+            }
+        }
+        parents.push((level, HashMap::new()));
+    }
+}
+
+/// Normalize the capitalization of heading text.
+///
+/// Only plain text is changed; code spans, attributes and protected words keep
+/// their spelling, as do words with inner capitals (`API`, `GitHub`). Every
+/// changed heading is reported as a diagnostic.
+pub fn apply_heading_case<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    config: &HeadingConfig,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    if config.case == HeadingCase::Preserve {
+        return events;
+    }
+
+    let mut result = Vec::with_capacity(events.len());
+    let mut heading: Option<(usize, String, String)> = None;
+    let mut first_word = true;
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                heading = Some((
+                    line_number(source, range.start),
+                    String::new(),
+                    String::new(),
+                ));
+                first_word = true;
+                result.push((event, range));
+            }
+            Event::Text(text) if heading.is_some() => {
+                let cased = change_case(&text, config, &mut first_word);
+                if let Some((_, before, after)) = heading.as_mut() {
+                    before.push_str(&text);
+                    after.push_str(&cased);
+                }
+                result.push((Event::Text(CowStr::from(cased)), range));
+            }
+            Event::Code(code) if heading.is_some() => {
+                if let Some((_, before, after)) = heading.as_mut() {
+                    let span = format!("`{code}`");
+                    before.push_str(&span);
+                    after.push_str(&span);
+                }
+                first_word = false;
+                result.push((Event::Code(code), range));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((line, before, after)) = heading.take() {
+                    if before != after {
+                        let case = match config.case {
+                            HeadingCase::Title => "title",
+                            _ => "sentence",
+                        };
+                        diagnostics.add(
+                            Diagnostic::new(
+                                Severity::Info,
+                                DiagnosticKind::Heading,
+                                line,
+                                format!("Changed heading to {case} case"),
+                            )
+                            .with_snippet(format!("{before} → {after}")),
+                        );
+                    }
+                }
+                result.push((event, range));
+            }
+            event => result.push((event, range)),
+        }
+    }
+
+    result
+}
+
+/// Change the case of the words in a piece of heading text.
+///
+/// `first_word` tracks whether the heading's first word is still to come,
+/// across the text events of one heading.
+fn change_case(text: &str, config: &HeadingConfig, first_word: &mut bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let space_end = rest[word_end..]
+            .find(|c: char| !c.is_whitespace())
+            .map_or(rest.len(), |pos| word_end + pos);
+        let word = &rest[..word_end];
+
+        if word.chars().any(char::is_alphabetic) {
+            result.push_str(&change_word_case(word, config, *first_word));
+            *first_word = false;
+        } else {
+            // Section numbers and punctuation don't count as the first word
+            result.push_str(word);
+        }
+        result.push_str(&rest[word_end..space_end]);
+        rest = &rest[space_end..];
+    }
+
+    result
+}
+
+/// Change the case of a single word according to the heading case.
+fn change_word_case(word: &str, config: &HeadingConfig, is_first: bool) -> String {
+    let core = word.trim_matches(|c: char| !c.is_alphanumeric());
+    if let Some(protected) = config
+        .protected_words
+        .iter()
+        .find(|protected| protected.to_lowercase() == core.to_lowercase())
+    {
+        return word.replacen(core, protected, 1);
+    }
+
+    // Acronyms and names with inner capitals (`API`, `GitHub`) are left alone
+    if core.chars().skip(1).any(char::is_uppercase) {
+        return word.to_string();
+    }
+
+    let lower = word.to_lowercase();
+    let capitalize = match config.case {
+        HeadingCase::Title => is_first || !MINOR_WORDS.contains(&core.to_lowercase().as_str()),
+        _ => is_first,
+    };
+    if !capitalize {
+        return lower;
+    }
+
+    let mut capitalized = String::with_capacity(lower.len());
+    let mut done = false;
+    for ch in lower.chars() {
+        if !done && ch.is_alphabetic() {
+            capitalized.extend(ch.to_uppercase());
+            done = true;
+        } else {
+            capitalized.push(ch);
+        }
+    }
+    capitalized
+}
+
+/// Add or strip explicit heading IDs (`{#id}`) and report ID collisions.
+///
+/// Added IDs are slugs of the heading text, made unique against every other
+/// ID in the document, so existing links keep working when the wording of a
+/// heading changes later.
+pub fn manage_heading_ids<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    config: &HeadingConfig,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut headings: Vec<(usize, Option<String>, String)> = Vec::new();
+    let mut in_heading = false;
+    for (index, (event, _)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Heading { id, .. }) => {
+                in_heading = true;
+                headings.push((index, id.as_ref().map(ToString::to_string), String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => in_heading = false,
+            Event::Text(text) | Event::Code(text) if in_heading => {
+                if let Some((_, _, heading_text)) = headings.last_mut() {
+                    heading_text.push_str(text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Explicit IDs are taken first so generated ones never collide with them
+    let mut slugger = Slugger::with_style(config.slug_style);
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    for (index, id, _) in &headings {
+        let Some(id) = id else { continue };
+        let line = line_number(source, events[*index].1.start);
+        if let Some(first) = first_seen.get(id.as_str()) {
+            diagnostics.warn(
+                DiagnosticKind::Heading,
+                line,
+                format!("Duplicate heading ID '{id}' (first used at line {first})"),
+            );
+        } else {
+            first_seen.insert(id, line);
+        }
+        slugger.unique(id);
+    }
+
+    let ids: Vec<(usize, Option<String>)> = match config.ids {
+        HeadingIds::Preserve => return events,
+        HeadingIds::Remove => headings
+            .iter()
+            .map(|(index, _, _)| (*index, None))
+            .collect(),
+        HeadingIds::Add => headings
+            .iter()
+            .filter(|(_, id, _)| id.is_none())
+            .map(|(index, _, text)| (*index, Some(slugger.slug(text))))
+            .collect(),
+    };
+
+    let mut events = events;
+    for (index, new_id) in ids {
+        if let Event::Start(Tag::Heading { id, .. }) = &mut events[index].0 {
+            *id = new_id.filter(|id| !id.is_empty()).map(CowStr::from);
+        }
+    }
+    events
+}
+
+/// Insert, renumber or strip section numbers on headings.
+///
+/// Headings from `start_level` down to level `max_depth` are numbered.
+/// Existing numbers in the configured format are stripped first, so
+/// renumbering is idempotent (see [`strip_section_number`]). `preserve`
+/// never touches heading text, so stale numbers are only dropped by
+/// `remove`. Heading attributes (`{#id}`) are untouched since they are not
+/// part of the heading text.
+pub fn number_headings<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    config: &NumberingConfig,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    if config.mode == NumberingMode::Preserve {
+        return events;
+    }
+
+    let mut result = Vec::with_capacity(events.len());
+    let mut counters = [0usize; 6];
+    let mut dotted: Option<String> = None;
+    let mut at_heading_start = false;
+    // Text at the start of the current heading, with its range
+    let mut leading: Option<(String, Range<usize>)> = None;
+
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = level as usize;
+                dotted = (level <= config.max_depth)
+                    .then(|| level.checked_sub(config.start_level))
+                    .flatten()
+                    .filter(|&depth| depth < counters.len())
+                    .map(|depth| {
+                        counters[depth] += 1;
+                        counters[depth + 1..].fill(0);
+                        let parts: Vec<String> =
+                            counters[..=depth].iter().map(ToString::to_string).collect();
+                        parts.join(".")
+                    });
+                at_heading_start = true;
+                result.push((event, range));
+            }
+            Event::Text(text) if at_heading_start => {
+                // Escapes split the text (`1\.` parses as `1` and `.`)
+                match &mut leading {
+                    Some((leading, _)) => leading.push_str(&text),
+                    None => leading = Some((text.to_string(), range)),
+                }
+            }
+            event if at_heading_start => {
+                at_heading_start = false;
+                let dotted = dotted.take();
+                let number = dotted
+                    .as_deref()
+                    .filter(|_| config.mode == NumberingMode::Add)
+                    .map(|dotted| config.format.replace(NUMBER_PLACEHOLDER, dotted));
+
+                if let Some((text, text_range)) = leading.take() {
+                    let text = dotted.as_deref().map_or(text.as_str(), |dotted| {
+                        strip_section_number(&text, &config.format, dotted)
+                    });
+                    let text = number
+                        .map_or_else(|| text.to_string(), |number| format!("{number} {text}"));
+                    result.push((Event::Text(CowStr::from(text)), text_range));
+                } else if let Some(number) = number {
+                    // Heading starts with markup (or is empty); number goes in front
+                    let text = if matches!(event, Event::End(_)) {
+                        number
+                    } else {
+                        format!("{number} ")
+                    };
+                    result.push((Event::Text(CowStr::from(text)), range.clone()));
+                }
+                result.push((event, range));
+            }
+            event => result.push((event, range)),
+        }
+    }
+
+    result
+}
+
+/// Strip a leading section number in the given format from heading text.
+///
+/// `dotted` is the number this heading gets (`1.2`). A number with the
+/// same count of components is taken to be an earlier one from this tool,
+/// so renumbering after inserting a section works. With a bare
+/// `{number}` format that is ambiguous (`# 2024 Roadmap`, `## 1.5 Notes`),
+/// so only the exact number is stripped there.
+fn strip_section_number<'t>(text: &'t str, format: &str, dotted: &str) -> &'t str {
+    let (prefix, suffix) = format
+        .split_once(NUMBER_PLACEHOLDER)
+        .unwrap_or((format, ""));
+    let Some(rest) = text.strip_prefix(prefix) else {
+        return text;
+    };
+
+    let digits = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let number = &rest[..digits];
+    if !number.starts_with(|c: char| c.is_ascii_digit()) {
+        return text;
+    }
+
+    // A trailing dot in the number may belong to the suffix (`1.2.` with `{number}.`)
+    let (number, rest) = match rest[digits..].strip_prefix(suffix) {
+        Some(rest) => (number, Some(rest)),
+        None if number.ends_with('.') => (
+            &number[..digits - 1],
+            rest[digits - 1..].strip_prefix(suffix),
+        ),
+        None => (number, None),
+    };
+    let number = number.trim_end_matches('.');
+
+    let distinctive = !format.replace(NUMBER_PLACEHOLDER, "").trim().is_empty();
+    let generated =
+        number == dotted || (distinctive && number.split('.').count() == dotted.split('.').count());
+    match rest {
+        Some(rest) if generated && (rest.is_empty() || rest.starts_with(char::is_whitespace)) => {
+            rest.trim_start()
+        }
+        _ => text,
+    }
+}
 
 /// Format headings in markdown content.
 ///
-/// Ensures consistent spacing before/after headings and space after `#` symbols.
-pub fn format_headings(content: &str, config: &HeadingConfig) -> String {
+/// Ensures consistent spacing before/after headings and space after `#` symbols,
+/// and renders top-level headings in the style resolved in `headings`.
+pub fn format_headings(content: &str, config: &HeadingConfig, headings: &[HeadingInfo]) -> String {
     let lines: Vec<&str> = content.lines().collect();
+    let heading_lines = heading_lines(content);
     let mut result: Vec<String> = Vec::new();
+    let mut top_level = headings.iter();
+    let mut in_code_block = false;
+    let mut previous_heading = false;
     let mut i = 0;
 
     while i < lines.len() {
         let line = lines[i];
         let trimmed = line.trim();
 
-        if is_heading(trimmed) {
-            // Add blank lines before heading
-            if i > 0 && !result.is_empty() {
-                // Count existing blank lines
-                let mut blank_count = 0;
-                for j in (0..result.len()).rev() {
-                    if result[j].trim().is_empty() {
-                        blank_count += 1;
-                    } else {
-                        break;
-                    }
-                }
+        // Lines inside fenced code are never headings
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+        if in_code_block {
+            previous_heading = false;
+            result.push(line.to_string());
+            i += 1;
+            continue;
+        }
 
-                // Adjust to desired count
-                while blank_count < config.blank_lines_before {
-                    result.push(String::new());
-                    blank_count += 1;
-                }
-                while blank_count > config.blank_lines_before {
-                    result.pop();
-                    blank_count -= 1;
-                }
+        // Headings in lists and block quotes keep their line prefix and
+        // spacing; only the heading text after the prefix is formatted
+        if let Some(&(false, column)) = heading_lines.get(&i) {
+            let (prefix, heading) = line.split_at(column);
+            if is_heading(heading) {
+                let normalized = normalize_nested(heading, config);
+                result.push(format!("{prefix}{normalized}"));
+                previous_heading = false;
+                i += 1;
+                continue;
             }
+        }
+
+        if let (Some(&(top, _)), true) = (heading_lines.get(&i), is_heading(trimmed)) {
+            let level = trimmed.len() - trimmed.trim_start_matches('#').len();
+            let (before, after) = config.spacing(level);
 
-            // Normalize heading (ensure space after #)
-            let normalized = if config.space_after_hash {
-                normalize_heading(trimmed)
+            // Replace the blank lines before the heading with the configured count
+            let blank_count = result
+                .iter()
+                .rev()
+                .take_while(|line| line.trim().is_empty())
+                .count();
+            let wanted = if blank_count == result.len() {
+                config.blank_lines_at_start
+            } else if previous_heading {
+                config.blank_lines_between.unwrap_or(before)
             } else {
-                trimmed.to_string()
+                before
             };
-            result.push(normalized);
+            result.truncate(result.len() - blank_count);
+            result.resize(result.len() + wanted, String::new());
 
-            // Add blank lines after heading
-            for _ in 0..config.blank_lines_after {
-                result.push(String::new());
+            let info = if top { top_level.next() } else { None };
+            match info {
+                Some(info) if info.setext => {
+                    result.extend(setext_heading(&normalize(trimmed, config), info.level));
+                }
+                Some(info) if info.closing_hashes => {
+                    result.push(add_closing_hashes(&normalize(trimmed, config)));
+                }
+                Some(_) => result.push(normalize(trimmed, config)),
+                None => result.push(normalize_nested(trimmed, config)),
             }
 
+            previous_heading = true;
+
             // Skip existing blank lines after heading
             i += 1;
             while i < lines.len() && lines[i].trim().is_empty() {
                 i += 1;
             }
+
+            // Add blank lines after heading, unless it ends the document
+            if i < lines.len() {
+                result.resize(result.len() + after, String::new());
+            }
             continue;
         }
 
+        if !trimmed.is_empty() {
+            previous_heading = false;
+        }
         result.push(line.to_string());
         i += 1;
     }
@@ -68,12 +740,140 @@ pub fn format_headings(content: &str, config: &HeadingConfig) -> String {
     result.join("\n")
 }
 
+/// Normalize an ATX heading line: space after `#` and compact attributes.
+fn normalize(heading: &str, config: &HeadingConfig) -> String {
+    let normalized = if config.space_after_hash {
+        normalize_heading(heading)
+    } else {
+        heading.to_string()
+    };
+    compact_attributes(&normalized)
+}
+
+/// Normalize a heading nested in a list or block quote.
+///
+/// Nested headings have no [`HeadingInfo`], so they only follow the
+/// configured default for closing hashes.
+fn normalize_nested(heading: &str, config: &HeadingConfig) -> String {
+    let normalized = normalize(heading, config);
+    if config.closing_hashes == ClosingHashes::Add {
+        add_closing_hashes(&normalized)
+    } else {
+        normalized
+    }
+}
+
+/// Lines where an ATX heading starts, whether it is a top-level one, and the
+/// column where the heading starts after any list marker or `>`.
+///
+/// Found by parsing `content`, so `#` lines in HTML blocks are not
+/// mistaken for headings and top-level headings line up with the
+/// [`HeadingInfo`] collected from the events.
+fn heading_lines(content: &str) -> HashMap<usize, (bool, usize)> {
+    let mut lines = HashMap::new();
+    let mut container_depth = 0usize;
+    for (event, range) in parse_markdown(content) {
+        match event {
+            Event::Start(Tag::BlockQuote(_) | Tag::Item | Tag::FootnoteDefinition(_)) => {
+                container_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_) | TagEnd::Item | TagEnd::FootnoteDefinition) => {
+                container_depth = container_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::Heading { .. }) => {
+                let line_start = content[..range.start].rfind('\n').map_or(0, |pos| pos + 1);
+                lines.insert(
+                    line_number(content, range.start) - 1,
+                    (container_depth == 0, range.start - line_start),
+                );
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
 /// Check if a line is a heading.
 fn is_heading(line: &str) -> bool {
     let trimmed = line.trim();
     trimmed.starts_with('#') && !trimmed.starts_with("```")
 }
 
+/// Render an ATX heading line as a setext heading (text and underline).
+///
+/// Text starting like a list item (`1. Intro` from section numbering,
+/// `1.5x`, `-x`) gets its marker escaped, since on its own line it would be
+/// read as one, or rewritten into one by the preprocessor.
+fn setext_heading(line: &str, level: usize) -> [String; 2] {
+    let text = line.trim_start_matches('#').trim();
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    let marker = if (1..10).contains(&digits) && text[digits..].starts_with(['.', ')']) {
+        Some(digits)
+    } else if text.starts_with(['-', '+']) {
+        Some(0)
+    } else {
+        None
+    };
+    let text = marker.map_or_else(
+        || text.to_string(),
+        |at| format!("{}\\{}", &text[..at], &text[at..]),
+    );
+    let underline = if level == 1 { "=" } else { "-" };
+    let width = text.chars().count().max(3);
+    [text, underline.repeat(width)]
+}
+
+/// Split a heading's trailing attribute block (`{ #id .class }`) from its text.
+fn split_attributes(line: &str) -> (&str, Option<&str>) {
+    let line = line.trim_end();
+    if line.ends_with('}') {
+        if let Some(pos) = line.rfind('{') {
+            return (line[..pos].trim_end(), Some(&line[pos..]));
+        }
+    }
+    (line, None)
+}
+
+/// Render a heading's attribute block without inner padding: `{#id .class}`.
+fn compact_attributes(line: &str) -> String {
+    match split_attributes(line) {
+        (text, Some(attributes)) => {
+            let inner: Vec<&str> = attributes[1..attributes.len() - 1]
+                .split_whitespace()
+                .collect();
+            format!("{text} {{{}}}", inner.join(" "))
+        }
+        (text, None) => text.to_string(),
+    }
+}
+
+/// Check if an ATX heading source line ends with a closing `#` sequence.
+fn has_closing_hashes(line: &str) -> bool {
+    let (text, _) = split_attributes(line.trim());
+    let content = text.trim_start_matches('#');
+    let without_closing = content.trim_end_matches('#');
+
+    // `# C#` has no closing sequence; one needs a space before it
+    without_closing.len() < content.len()
+        && (without_closing.is_empty() || without_closing.ends_with(char::is_whitespace))
+}
+
+/// Close an ATX heading line with as many hashes as it opens with.
+///
+/// The closing sequence goes before any attribute block: `## Title ## {#id}`.
+fn add_closing_hashes(line: &str) -> String {
+    let hashes = &line[..line.len() - line.trim_start_matches('#').len()];
+    let (text, attributes) = split_attributes(line);
+
+    if text.trim_start_matches('#').trim().is_empty() {
+        return line.to_string();
+    }
+    attributes.map_or_else(
+        || format!("{text} {hashes}"),
+        |attributes| format!("{text} {hashes} {attributes}"),
+    )
+}
+
 /// Normalize heading to ensure space after `#` symbols.
 fn normalize_heading(line: &str) -> String {
     let trimmed = line.trim();
@@ -101,6 +901,7 @@ fn normalize_heading(line: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{HeadingLevels, LevelSpacing};
 
     #[test]
     fn test_normalize_heading() {
@@ -124,9 +925,10 @@ mod tests {
             blank_lines_before: 1,
             blank_lines_after: 1,
             space_after_hash: true,
+            ..Default::default()
         };
 
-        let result = format_headings(input, &config);
+        let result = format_headings(input, &config, &[]);
         assert!(result.contains("# Heading"));
     }
 
@@ -137,13 +939,439 @@ mod tests {
             blank_lines_before: 1,
             blank_lines_after: 1,
             space_after_hash: true,
+            ..Default::default()
         };
 
-        let result = format_headings(input, &config);
+        let result = format_headings(input, &config, &[]);
         eprintln!("INPUT:\n{input}");
         eprintln!("\nOUTPUT:\n{result}");
 
         // Bold text should not be affected
         assert!(result.contains("**Table of Contents:**"));
     }
+
+    #[test]
+    fn test_format_headings_setext() {
+        let input = "# Title\n\n## Section\n\n### Deep";
+        let headings = [
+            HeadingInfo {
+                level: 1,
+                setext: true,
+                closing_hashes: false,
+            },
+            HeadingInfo {
+                level: 2,
+                setext: true,
+                closing_hashes: false,
+            },
+            HeadingInfo {
+                level: 3,
+                setext: false,
+                closing_hashes: false,
+            },
+        ];
+
+        let result = format_headings(input, &HeadingConfig::default(), &headings);
+        assert_eq!(result, "Title\n=====\n\nSection\n-------\n\n### Deep");
+    }
+
+    #[test]
+    fn test_setext_heading_escapes_markers() {
+        assert_eq!(
+            setext_heading("# 1. Intro {#intro}", 1)[0],
+            "1\\. Intro {#intro}"
+        );
+        assert_eq!(setext_heading("## 2) Next", 2)[0], "2\\) Next");
+        assert_eq!(setext_heading("## - Dash", 2)[0], "\\- Dash");
+        assert_eq!(setext_heading("# 2024 Roadmap", 1)[0], "2024 Roadmap");
+        assert_eq!(setext_heading("# 1.5x Faster", 1)[0], "1\\.5x Faster");
+    }
+
+    #[test]
+    fn test_format_headings_skips_html() {
+        let input = "<div>\n# inside html\n</div>\n\n# Title\n\n## Two";
+        let headings = [
+            HeadingInfo {
+                level: 1,
+                setext: true,
+                closing_hashes: false,
+            },
+            HeadingInfo {
+                level: 2,
+                setext: false,
+                closing_hashes: false,
+            },
+        ];
+
+        let result = format_headings(input, &HeadingConfig::default(), &headings);
+        assert_eq!(
+            result,
+            "<div>\n# inside html\n</div>\n\nTitle\n=====\n\n## Two"
+        );
+    }
+
+    #[test]
+    fn test_format_headings_context_spacing() {
+        let config = HeadingConfig {
+            blank_lines_at_start: 1,
+            blank_lines_between: Some(0),
+            levels: HeadingLevels {
+                h2: LevelSpacing {
+                    blank_lines_before: Some(2),
+                    blank_lines_after: None,
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let input = "# Title\n## Intro\nText\n## Usage\n\n\n### Last\n";
+        let result = format_headings(input, &config, &[]);
+        assert_eq!(
+            result,
+            "\n# Title\n## Intro\n\nText\n\n\n## Usage\n### Last"
+        );
+    }
+
+    #[test]
+    fn test_format_headings_skips_code() {
+        let input = "````\n# comment\n````";
+        let result = format_headings(input, &HeadingConfig::default(), &[]);
+        assert_eq!(result, input);
+    }
+
+    #[test]
+    fn test_process_heading_events_preserve() {
+        let source = "Title\n=====\n\n## Atx\n\nMulti\nline\n---\n\n> # Quoted";
+        let events = pulldown_cmark::Parser::new(source)
+            .into_offset_iter()
+            .collect();
+        let config = HeadingConfig {
+            style: HeadingStyle::Preserve,
+            ..Default::default()
+        };
+
+        let (events, headings) = process_heading_events(events, source, &config);
+        let setext: Vec<bool> = headings.iter().map(|info| info.setext).collect();
+        assert_eq!(setext, [true, false, true]);
+        assert!(!events.iter().any(|(event, _)| *event == Event::SoftBreak));
+    }
+
+    #[test]
+    fn test_has_closing_hashes() {
+        assert!(has_closing_hashes("## Title ##"));
+        assert!(has_closing_hashes("## Title # {#id}"));
+        assert!(!has_closing_hashes("# C#"));
+        assert!(!has_closing_hashes("## Title"));
+    }
+
+    #[test]
+    fn test_add_closing_hashes() {
+        assert_eq!(add_closing_hashes("## Title"), "## Title ##");
+        assert_eq!(
+            add_closing_hashes("### Title { #id }"),
+            "### Title ### { #id }"
+        );
+    }
+
+    #[test]
+    fn test_format_headings_closing_hashes_add() {
+        let config = HeadingConfig {
+            closing_hashes: ClosingHashes::Add,
+            ..Default::default()
+        };
+        let result = format_headings("# Title\n\n- ## Nested\n\n> # Quoted", &config, &[]);
+        assert_eq!(result, "# Title #\n\n- ## Nested ##\n\n> # Quoted #");
+    }
+
+    fn numbered(source: &str, config: &NumberingConfig) -> Vec<String> {
+        let events = pulldown_cmark::Parser::new_ext(
+            source,
+            pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES,
+        )
+        .into_offset_iter()
+        .collect();
+
+        heading_texts(number_headings(events, config))
+    }
+
+    fn heading_texts(events: Vec<(Event<'_>, Range<usize>)>) -> Vec<String> {
+        let mut headings = Vec::new();
+        let mut in_heading = false;
+        for (event, _) in events {
+            match event {
+                Event::Start(Tag::Heading { .. }) => {
+                    headings.push(String::new());
+                    in_heading = true;
+                }
+                Event::End(TagEnd::Heading(_)) => in_heading = false,
+                Event::Text(text) | Event::Code(text) if in_heading => {
+                    if let Some(heading) = headings.last_mut() {
+                        heading.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        headings
+    }
+
+    #[test]
+    fn test_number_headings() {
+        let config = NumberingConfig {
+            mode: NumberingMode::Add,
+            start_level: 2,
+            max_depth: 3,
+            ..Default::default()
+        };
+        // `max_depth` is a heading level, so H4 is not numbered
+        let source =
+            "# Title\n\n## Intro\n\n### 9.9. Stale\n\n### `Code` {#keep}\n\n## Next\n\n#### Deep";
+
+        assert_eq!(
+            numbered(source, &config),
+            [
+                "Title",
+                "1. Intro",
+                "1.1. Stale",
+                "1.2. Code",
+                "2. Next",
+                "Deep"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_number_headings_idempotent_and_remove() {
+        let config = NumberingConfig {
+            mode: NumberingMode::Add,
+            ..Default::default()
+        };
+        let source = "# 1. Intro\n\n## 1.1. Scope\n\n# 2. Next";
+        assert_eq!(
+            numbered(source, &config),
+            ["1. Intro", "1.1. Scope", "2. Next"]
+        );
+
+        let config = NumberingConfig {
+            mode: NumberingMode::Remove,
+            ..Default::default()
+        };
+        assert_eq!(numbered(source, &config), ["Intro", "Scope", "Next"]);
+        assert_eq!(numbered("# 2024 Roadmap", &config), ["2024 Roadmap"]);
+
+        // Only `remove` strips numbers; `preserve` leaves them as written
+        assert_eq!(
+            numbered(source, &NumberingConfig::default()),
+            ["1. Intro", "1.1. Scope", "2. Next"]
+        );
+    }
+
+    #[test]
+    fn test_number_headings_keeps_leading_numbers() {
+        let config = NumberingConfig {
+            mode: NumberingMode::Add,
+            format: String::from("{number}"),
+            ..Default::default()
+        };
+        let source = "# 2024 Roadmap\n\n## 3 Things\n\n## 1.2 Done";
+        assert_eq!(
+            numbered(source, &config),
+            ["1 2024 Roadmap", "1.1 3 Things", "1.2 Done"]
+        );
+        assert_eq!(
+            numbered("# 1 2024 Roadmap\n\n## 1.1 3 Things", &config),
+            ["1 2024 Roadmap", "1.1 3 Things"]
+        );
+
+        // Headings out of range, even with a level 0 start, are left alone
+        let config = NumberingConfig {
+            mode: NumberingMode::Add,
+            start_level: 0,
+            ..Default::default()
+        };
+        assert_eq!(numbered("###### Deep", &config), ["Deep"]);
+    }
+
+    #[test]
+    fn test_strip_section_number() {
+        assert_eq!(
+            strip_section_number("1.2. Intro", "{number}.", "3.4"),
+            "Intro"
+        );
+        assert_eq!(
+            strip_section_number("1.2 Intro", "{number}", "1.2"),
+            "Intro"
+        );
+        assert_eq!(
+            strip_section_number("1.5 Notes", "{number}", "1.2"),
+            "1.5 Notes"
+        );
+        assert_eq!(
+            strip_section_number("§ 3 Intro", "§ {number}", "1"),
+            "Intro"
+        );
+        assert_eq!(
+            strip_section_number("1.5x Faster", "{number}.", "1.5"),
+            "1.5x Faster"
+        );
+        assert_eq!(
+            strip_section_number("2024. Roadmap", "{number}.", "1.1"),
+            "2024. Roadmap"
+        );
+    }
+
+    fn cased(source: &str, config: &HeadingConfig) -> (Vec<String>, Diagnostics) {
+        let events = pulldown_cmark::Parser::new(source)
+            .into_offset_iter()
+            .collect();
+        let mut diagnostics = Diagnostics::new();
+
+        let events = apply_heading_case(events, source, config, &mut diagnostics);
+        (heading_texts(events), diagnostics)
+    }
+
+    #[test]
+    fn test_heading_sentence_case() {
+        let config = HeadingConfig {
+            case: HeadingCase::Sentence,
+            protected_words: vec![String::from("Rust"), String::from("beautiful-md")],
+            ..Default::default()
+        };
+        let source = "# Getting Started With Rust\n\n## The API Reference\n\n## Using `Config` In Beautiful-MD\n\n## 1.2. Section Title\n\nBody Text Stays";
+        let (headings, diagnostics) = cased(source, &config);
+
+        assert_eq!(
+            headings,
+            [
+                "Getting started with Rust",
+                "The API reference",
+                "Using Config in beautiful-md",
+                "1.2. Section title"
+            ]
+        );
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(diagnostics.messages()[1].line, 3);
+    }
+
+    #[test]
+    fn test_heading_title_case() {
+        let config = HeadingConfig {
+            case: HeadingCase::Title,
+            ..Default::default()
+        };
+        let (headings, diagnostics) = cased("# the state of the art\n\n## Already Fine", &config);
+
+        assert_eq!(headings, ["The State of the Art", "Already Fine"]);
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    fn outline(source: &str, config: &HeadingConfig) -> (Vec<usize>, Diagnostics) {
+        let events = pulldown_cmark::Parser::new(source)
+            .into_offset_iter()
+            .collect();
+        let mut diagnostics = Diagnostics::new();
+
+        let levels = check_heading_hierarchy(events, source, config, &mut diagnostics)
+            .into_iter()
+            .filter_map(|(event, _)| match event {
+                Event::Start(Tag::Heading { level, .. }) => Some(level as usize),
+                _ => None,
+            })
+            .collect();
+        (levels, diagnostics)
+    }
+
+    #[test]
+    fn test_heading_hierarchy_lints() {
+        let source = "# Title\n\n### Skipped\n\n## Setup:\n\n### Notes\n\n## Usage\n\n### Notes\n\n### notes\n\n# Second\n\n- # In list\n\n> # Quoted";
+        let (levels, diagnostics) = outline(source, &HeadingConfig::default());
+
+        assert_eq!(levels, [1, 3, 2, 3, 2, 3, 3, 1, 1, 1]);
+        let messages: Vec<(usize, &str)> = diagnostics
+            .messages()
+            .iter()
+            .map(|d| (d.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (3, "Heading level jumps from H1 to H3"),
+                (5, "Heading ends with punctuation ':'"),
+                (
+                    13,
+                    "Duplicate heading under the same parent (first at line 11)"
+                ),
+                (15, "Multiple top-level headings (first H1 at line 1)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heading_hierarchy_fix_increments() {
+        let config = HeadingConfig {
+            fix_increments: true,
+            ..Default::default()
+        };
+        let source = "# A\n\n### B\n\n#### C\n\n## D\n\n###### E";
+        let (levels, diagnostics) = outline(source, &config);
+
+        assert_eq!(levels, [1, 2, 3, 2, 3]);
+        assert_eq!(diagnostics.by_severity(Severity::Warning).len(), 0);
+        assert_eq!(diagnostics.by_severity(Severity::Info).len(), 2);
+    }
+
+    fn heading_ids(source: &str, config: &HeadingConfig) -> (Vec<Option<String>>, Diagnostics) {
+        let events = pulldown_cmark::Parser::new_ext(
+            source,
+            pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES,
+        )
+        .into_offset_iter()
+        .collect();
+        let mut diagnostics = Diagnostics::new();
+
+        let ids = manage_heading_ids(events, source, config, &mut diagnostics)
+            .into_iter()
+            .filter_map(|(event, _)| match event {
+                Event::Start(Tag::Heading { id, .. }) => Some(id.map(|id| id.to_string())),
+                _ => None,
+            })
+            .collect();
+        (ids, diagnostics)
+    }
+
+    #[test]
+    fn test_manage_heading_ids() {
+        let source = "# Intro\n\n## Install {#setup}\n\n## Setup\n\n## Other {#setup}\n";
+        let config = HeadingConfig {
+            ids: HeadingIds::Add,
+            ..Default::default()
+        };
+
+        let (ids, diagnostics) = heading_ids(source, &config);
+        assert_eq!(
+            ids,
+            [Some("intro"), Some("setup"), Some("setup-2"), Some("setup")]
+                .map(|id| id.map(String::from))
+        );
+        assert_eq!(
+            diagnostics.messages()[0].message,
+            "Duplicate heading ID 'setup' (first used at line 3)"
+        );
+
+        let config = HeadingConfig {
+            ids: HeadingIds::Remove,
+            ..Default::default()
+        };
+        let (ids, _) = heading_ids(source, &config);
+        assert!(ids.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_compact_attributes() {
+        assert_eq!(
+            compact_attributes("## Title { #id .x }"),
+            "## Title {#id .x}"
+        );
+        assert_eq!(compact_attributes("## Title"), "## Title");
+    }
 }
@@ -0,0 +1,376 @@
+//! Image formatting module.
+//!
+//! Reports images without alt text and mixed HTML and Markdown image
+//! syntax, and optionally converts simple `<img>` tags to Markdown.
+//!
+//! Missing local image files are not checked here: `linkcheck` reports
+//! them for both syntaxes, next to missing link targets.
+
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+
+use super::line_number;
+use crate::config::ImageConfig;
+use crate::diagnostics::{DiagnosticKind, Diagnostics};
+use crate::formatter::parse_markdown;
+
+/// `<img>` attributes that Markdown image syntax can express.
+const SIMPLE_ATTRIBUTES: [&str; 3] = ["src", "alt", "title"];
+
+/// An HTML `<img>` tag.
+struct ImgTag {
+    attributes: Vec<(String, String)>,
+}
+
+impl ImgTag {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Attributes that would be lost in Markdown syntax.
+    fn extra_attributes(&self) -> Vec<&str> {
+        self.attributes
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .filter(|key| !SIMPLE_ATTRIBUTES.contains(key))
+            .collect()
+    }
+
+    /// The Markdown image events for this tag.
+    fn to_events<'a>(&self, range: &Range<usize>) -> Vec<(Event<'a>, Range<usize>)> {
+        let text = |name| CowStr::from(self.get(name).unwrap_or_default().to_string());
+        let mut events = vec![(
+            Event::Start(Tag::Image {
+                link_type: LinkType::Inline,
+                dest_url: text("src"),
+                title: text("title"),
+                id: CowStr::Borrowed(""),
+            }),
+            range.clone(),
+        )];
+        events.extend(
+            alt_events(self.get("alt").unwrap_or_default())
+                .into_iter()
+                .map(|event| (event, range.clone())),
+        );
+        events.push((Event::End(TagEnd::Image), range.clone()));
+        events
+    }
+}
+
+/// The events of `alt` as literal Markdown text.
+///
+/// Every punctuation character is escaped and the result parsed, so the alt
+/// text comes out split into the same events a later run parses it into, and
+/// formatting the converted image again leaves it unchanged.
+fn alt_events(alt: &str) -> Vec<Event<'static>> {
+    let mut escaped = String::with_capacity(alt.len());
+    for c in alt.chars() {
+        if c.is_ascii_punctuation() {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    parse_markdown(&escaped)
+        .into_iter()
+        .map(|(event, _)| event)
+        .filter(|event| !matches!(event, Event::Start(_) | Event::End(_)))
+        .map(Event::into_static)
+        .collect()
+}
+
+/// Lint images and convert simple `<img>` tags when configured.
+///
+/// Images without alt text are warned about. `<img>` tags in a document
+/// that also uses `![alt](src)` are reported, and converted when
+/// `convert_html` is set and the tag has no attributes besides `src`, `alt`
+/// and `title`. Tags with size or other attributes are kept. Whether the
+/// image file exists is left to `linkcheck::check_links`.
+pub fn lint_images<'a>(
+    events: &[(Event<'a>, Range<usize>)],
+    source: &str,
+    config: &ImageConfig,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let has_markdown_images = events
+        .iter()
+        .any(|(event, _)| matches!(event, Event::Start(Tag::Image { .. })));
+
+    let mut result = Vec::with_capacity(events.len());
+    let mut alt: Option<(String, String)> = None;
+    let mut index = 0;
+
+    while index < events.len() {
+        let (event, range) = &events[index];
+        let line = line_number(source, range.start);
+
+        // A block that is just an `<img>` tag
+        if let [(Event::Start(Tag::HtmlBlock), _), (Event::Html(html), _), (Event::End(TagEnd::HtmlBlock), _), ..] =
+            &events[index..]
+        {
+            if let Some(img) = parse_img_tag(html) {
+                if check_img(&img, line, has_markdown_images, config, diagnostics) {
+                    result.push((Event::Start(Tag::Paragraph), range.clone()));
+                    result.extend(img.to_events(range));
+                    result.push((Event::End(TagEnd::Paragraph), range.clone()));
+                } else {
+                    result.extend(events[index..index + 3].iter().cloned());
+                }
+                index += 3;
+                continue;
+            }
+        }
+
+        match event {
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                alt = Some((dest_url.to_string(), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, alt_text)) = alt.as_mut() {
+                    alt_text.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Image) => {
+                if let Some((src, alt_text)) = alt.take() {
+                    if alt_text.trim().is_empty() {
+                        diagnostics.warn(
+                            DiagnosticKind::Image,
+                            line,
+                            format!("Image has no alt text: {src}"),
+                        );
+                    }
+                }
+            }
+            Event::InlineHtml(html) => {
+                if let Some(img) = parse_img_tag(html) {
+                    if check_img(&img, line, has_markdown_images, config, diagnostics) {
+                        result.extend(img.to_events(range));
+                        index += 1;
+                        continue;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        result.push((event.clone(), range.clone()));
+        index += 1;
+    }
+
+    result
+}
+
+/// Report issues with an `<img>` tag and decide whether to convert it.
+fn check_img(
+    img: &ImgTag,
+    line: usize,
+    has_markdown_images: bool,
+    config: &ImageConfig,
+    diagnostics: &mut Diagnostics,
+) -> bool {
+    let src = img.get("src").unwrap_or_default();
+    if img.get("alt").unwrap_or_default().trim().is_empty() {
+        diagnostics.warn(
+            DiagnosticKind::Image,
+            line,
+            format!("HTML image has no alt text: {src}"),
+        );
+    }
+
+    let extra = img.extra_attributes();
+    if config.convert_html && !src.is_empty() && extra.is_empty() {
+        diagnostics.info(
+            DiagnosticKind::Image,
+            line,
+            format!("Converted HTML <img> to Markdown: {src}"),
+        );
+        return true;
+    }
+
+    if has_markdown_images {
+        let reason = if extra.is_empty() {
+            String::new()
+        } else {
+            format!(" (kept for its {} attribute)", extra.join(", "))
+        };
+        diagnostics.info(
+            DiagnosticKind::Image,
+            line,
+            format!("HTML <img> mixed with Markdown images{reason}: {src}"),
+        );
+    }
+    false
+}
+
+/// Find the `src` of every `<img>` tag in `html`, with its byte offset.
+pub fn img_sources(html: &str) -> Vec<(usize, String)> {
+    let mut sources = Vec::new();
+    let mut start = 0;
+    while let Some(pos) = html[start..].find('<').map(|pos| start + pos) {
+        let end = html[pos..]
+            .find('>')
+            .map_or(html.len(), |end| pos + end + 1);
+        if let Some(src) = parse_img_tag(&html[pos..end]).and_then(|img| {
+            img.get("src")
+                .filter(|src| !src.is_empty())
+                .map(str::to_string)
+        }) {
+            sources.push((pos, src));
+        }
+        start = pos + 1;
+    }
+    sources
+}
+
+/// Parse HTML that is exactly one `<img>` tag.
+fn parse_img_tag(html: &str) -> Option<ImgTag> {
+    let html = html.trim();
+    let rest = html
+        .get(..4)
+        .filter(|start| start.eq_ignore_ascii_case("<img"))
+        .map(|_| &html[4..])?;
+    let rest = rest.strip_suffix('>')?;
+    let rest = rest.strip_suffix('/').unwrap_or(rest);
+    if !rest.starts_with(char::is_whitespace) && !rest.is_empty() {
+        return None;
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = rest.trim_start();
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        if name.is_empty() || name.contains(['<', '>', '"', '\'']) {
+            return None;
+        }
+        rest = rest[name_end..].trim_start();
+
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, tail) = if let Some(quote @ ('"' | '\'')) = after.chars().next() {
+                let end = after[1..].find(quote)? + 1;
+                (&after[1..end], &after[end + 1..])
+            } else {
+                let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                after.split_at(end)
+            };
+            rest = tail.trim_start();
+            decode_entities(value)
+        } else {
+            String::new()
+        };
+        attributes.push((name, value));
+    }
+
+    Some(ImgTag { attributes })
+}
+
+/// Decode the common character references in an attribute value.
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_img_tag() {
+        let img = parse_img_tag("<IMG src='a b.png' alt=\"A &amp; B\" width=100 />").unwrap();
+        assert_eq!(img.get("src"), Some("a b.png"));
+        assert_eq!(img.get("alt"), Some("A & B"));
+        assert_eq!(img.extra_attributes(), ["width"]);
+
+        assert!(parse_img_tag("<imgx src=a>").is_none());
+        assert!(parse_img_tag("<img src=a> text").is_none());
+    }
+
+    #[test]
+    fn test_img_sources() {
+        assert_eq!(
+            img_sources("<p><img src=\"a.png\"> <IMG alt=x/><img src='b c.png' /></p>"),
+            [(3, String::from("a.png")), (33, String::from("b c.png"))]
+        );
+    }
+
+    #[test]
+    fn test_converted_alt_is_stable() {
+        let mut config = crate::Config::default();
+        config.images.convert_html = true;
+        let input = "<img src=\"a.png\" alt=\"*star*\"> <img src=\"b.png\" alt=\"A [b] c\">\n";
+
+        let (once, _) = crate::formatter::format(input, &config).unwrap();
+        assert_eq!(
+            once.trim_end(),
+            "![\\*star\\*](a.png) ![A \\[b\\] c](b.png)"
+        );
+        let (twice, _) = crate::formatter::format(&once, &config).unwrap();
+        assert_eq!(twice, once);
+    }
+
+    #[test]
+    fn test_lint_images() {
+        let source =
+            "![](a.png) <img src=\"b.png\" alt=\"B\">\n\n<img src=\"c.png\" width=\"9\">\n";
+        let config = ImageConfig { convert_html: true };
+        let mut diagnostics = Diagnostics::new();
+        let events = lint_images(&parse_markdown(source), source, &config, &mut diagnostics);
+
+        let images = events
+            .iter()
+            .filter(|(event, _)| matches!(event, Event::Start(Tag::Image { .. })))
+            .count();
+        assert_eq!(images, 2);
+
+        let messages: Vec<_> = diagnostics
+            .messages()
+            .iter()
+            .map(|d| (d.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (1, "Image has no alt text: a.png"),
+                (1, "Converted HTML <img> to Markdown: b.png"),
+                (3, "HTML image has no alt text: c.png"),
+                (
+                    3,
+                    "HTML <img> mixed with Markdown images (kept for its width attribute): c.png"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_html_images_only() {
+        let source =
+            "<img src=\"missing.png\">\n\nSee <img src=\"b.png\" alt=\"B\" width=\"9\">.\n";
+        let mut diagnostics = Diagnostics::new();
+        let events = lint_images(
+            &parse_markdown(source),
+            source,
+            &ImageConfig::default(),
+            &mut diagnostics,
+        );
+
+        assert_eq!(events, parse_markdown(source));
+        let messages: Vec<_> = diagnostics
+            .messages()
+            .iter()
+            .map(|d| (d.line, d.message.as_str()))
+            .collect();
+        assert_eq!(messages, [(1, "HTML image has no alt text: missing.png")]);
+    }
+}
@@ -0,0 +1,778 @@
+//! Link formatting module.
+//!
+//! Converts between inline links (`[text](url)`) and reference links
+//! (`[text][label]` with a `[label]: url` definition), and gathers the
+//! reference definitions at the end of the document.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use pulldown_cmark::{BrokenLink, CowStr, Event, LinkType, Parser, Tag, TagEnd};
+
+use super::line_number;
+use super::list::sort_key;
+use crate::config::{
+    AutolinkStyle, LabelCase, LinkStyle, LinksConfig, ReferenceLabels, ReferencePlacement,
+};
+use crate::diagnostics::{DiagnosticKind, Diagnostics};
+use crate::formatter::parser_options;
+use crate::slug;
+
+/// URL schemes recognized in bare URLs.
+const URL_SCHEMES: [&str; 2] = ["https://", "http://"];
+
+/// Characters that are not part of a bare URL when they come last.
+const TRAILING_PUNCTUATION: [char; 11] = ['.', ',', ':', ';', '!', '?', '*', '_', '~', '\'', '"'];
+
+/// A link reference definition.
+#[derive(Debug, Clone)]
+struct Definition {
+    label: String,
+    url: String,
+    title: String,
+}
+
+/// Reference definitions of a document and the state of writing them.
+struct References<'c> {
+    config: &'c LinksConfig,
+
+    /// Definitions from the source, by normalized label.
+    source: HashMap<String, Definition>,
+
+    /// Label for each URL and title, so links to the same place share one.
+    labels: HashMap<(String, String), String>,
+
+    /// Normalized labels that new labels must not collide with.
+    taken: HashSet<String>,
+
+    /// Normalized labels used by at least one link.
+    referenced: HashSet<String>,
+
+    /// Normalized labels whose definition has been scheduled.
+    written: HashSet<String>,
+
+    /// Definitions waiting for the end of the section or document.
+    pending: Vec<Definition>,
+
+    next_number: usize,
+}
+
+/// Rewrite links in the configured style and gather reference definitions.
+///
+/// Reference definitions move to the end of the document, or to the end of
+/// the section that first uses them. Unused definitions and references to
+/// missing definitions are reported. Autolinks and links to anchors in the
+/// same document (`#section`) are always left inline.
+///
+/// Reference link brackets are written as raw markup, since the serializer
+/// would otherwise add its own definitions at the end of the document.
+pub fn convert_links<'a>(
+    events: &[(Event<'a>, Range<usize>)],
+    source: &str,
+    config: &LinksConfig,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    report_undefined(source, diagnostics);
+    let definitions = reference_definitions(source);
+    let mut references = References::new(&definitions, config);
+
+    let mut result = Vec::with_capacity(events.len());
+    let mut closers = Vec::new();
+
+    for (index, (event, range)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Heading { .. })
+                if config.reference_placement == ReferencePlacement::Section =>
+            {
+                references.flush(&mut result, range.start);
+            }
+            Event::Start(
+                Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }
+                | Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                },
+            ) => {
+                let image = matches!(event, Event::Start(Tag::Image { .. }));
+                let is_reference = matches!(
+                    link_type,
+                    LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut
+                );
+
+                if is_reference && config.style == LinkStyle::Inline {
+                    references.referenced.insert(normalize(id));
+                    result.push((inline_event(event), range.clone()));
+                    closers.push(None);
+                    continue;
+                }
+
+                let definition = references.source.get(&normalize(id)).cloned();
+                let closer = match definition {
+                    Some(definition) if is_reference => {
+                        let closer = match link_type {
+                            LinkType::Collapsed => String::from("][]"),
+                            LinkType::Shortcut => String::from("]"),
+                            _ => format!("][{}]", references.cased(id)),
+                        };
+                        references.schedule(definition);
+                        Some(closer)
+                    }
+                    _ if *link_type == LinkType::Inline
+                        && !image
+                        && config.style == LinkStyle::Reference
+                        && !dest_url.starts_with('#') =>
+                    {
+                        let label =
+                            references.label_for(dest_url, title, || link_text(&events[index..]));
+                        Some(format!("][{}]", references.cased(&label)))
+                    }
+                    _ => None,
+                };
+
+                if let Some(closer) = closer {
+                    let open = if image { "![" } else { "[" };
+                    result.push((Event::InlineHtml(CowStr::Borrowed(open)), range.clone()));
+                    closers.push(Some(closer));
+                    continue;
+                }
+                closers.push(None);
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                if let Some(Some(closer)) = closers.pop() {
+                    result.push((Event::InlineHtml(closer.into()), range.clone()));
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        result.push((event.clone(), range.clone()));
+    }
+
+    let end = events.last().map_or(0, |(_, range)| range.end);
+    references.finish(&mut result, end, &definitions, source, diagnostics);
+    result
+}
+
+impl<'c> References<'c> {
+    fn new(definitions: &[(Definition, usize)], config: &'c LinksConfig) -> Self {
+        let mut references = Self {
+            config,
+            source: HashMap::new(),
+            labels: HashMap::new(),
+            taken: HashSet::new(),
+            referenced: HashSet::new(),
+            written: HashSet::new(),
+            pending: Vec::new(),
+            next_number: 1,
+        };
+        for (definition, _) in definitions {
+            let key = normalize(&definition.label);
+            references
+                .labels
+                .entry((definition.url.clone(), definition.title.clone()))
+                .or_insert_with(|| definition.label.clone());
+            references.taken.insert(key.clone());
+            references.source.insert(key, definition.clone());
+        }
+        references
+    }
+
+    /// Label of a link converted to a reference, creating one if needed.
+    fn label_for(&mut self, url: &str, title: &str, text: impl FnOnce() -> String) -> String {
+        let key = (url.to_string(), title.to_string());
+        let label = if let Some(label) = self.labels.get(&key) {
+            label.clone()
+        } else {
+            let label = self.new_label(&text());
+            self.taken.insert(normalize(&label));
+            self.labels.insert(key, label.clone());
+            label
+        };
+
+        let definition = self
+            .source
+            .get(&normalize(&label))
+            .cloned()
+            .unwrap_or_else(|| Definition {
+                label: label.clone(),
+                url: url.to_string(),
+                title: title.to_string(),
+            });
+        self.schedule(definition);
+        label
+    }
+
+    /// Choose a label that is not taken yet for a link with the given text.
+    fn new_label(&mut self, text: &str) -> String {
+        let base = match self.config.reference_labels {
+            ReferenceLabels::Slug => slug::gitlab(text),
+            ReferenceLabels::Numbered => String::new(),
+        };
+
+        if base.is_empty() {
+            while self.taken.contains(&self.next_number.to_string()) {
+                self.next_number += 1;
+            }
+            return self.next_number.to_string();
+        }
+
+        let mut label = base.clone();
+        let mut suffix = 1;
+        while self.taken.contains(&label) {
+            label = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        label
+    }
+
+    /// Record that a link uses `definition`.
+    ///
+    /// Definitions from the source stay in document order at the end unless
+    /// they go with their section; new ones are queued in order of use.
+    fn schedule(&mut self, definition: Definition) {
+        let key = normalize(&definition.label);
+        self.referenced.insert(key.clone());
+        let section = self.config.reference_placement == ReferencePlacement::Section;
+        if (section || !self.source.contains_key(&key)) && self.written.insert(key) {
+            self.pending.push(definition);
+        }
+    }
+
+    /// Apply the configured label case.
+    fn cased(&self, label: &str) -> String {
+        match self.config.label_case {
+            LabelCase::Preserve => label.to_string(),
+            LabelCase::Lower => label.to_lowercase(),
+        }
+    }
+
+    /// Write the queued definitions as a block at `offset`.
+    fn flush(&mut self, result: &mut Vec<(Event<'_>, Range<usize>)>, offset: usize) {
+        let definitions = std::mem::take(&mut self.pending);
+        self.write(result, definitions, offset);
+    }
+
+    /// Write the remaining definitions at the end of the document.
+    ///
+    /// Source definitions no link uses are kept unless `remove_unused` is
+    /// set, and reported either way.
+    fn finish(
+        mut self,
+        result: &mut Vec<(Event<'_>, Range<usize>)>,
+        offset: usize,
+        definitions: &[(Definition, usize)],
+        source: &str,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let mut remaining = Vec::new();
+        for (definition, start) in definitions {
+            let key = normalize(&definition.label);
+            let line = line_number(source, *start);
+            if !self.referenced.contains(&key) {
+                if self.config.remove_unused {
+                    diagnostics.info(
+                        DiagnosticKind::Link,
+                        line,
+                        format!(
+                            "Removed unused link reference definition [{}]",
+                            definition.label
+                        ),
+                    );
+                    continue;
+                }
+                diagnostics.warn(
+                    DiagnosticKind::Link,
+                    line,
+                    format!("Unused link reference definition [{}]", definition.label),
+                );
+            } else if self.config.style == LinkStyle::Inline {
+                continue;
+            }
+            if self.written.insert(key) {
+                remaining.push(definition.clone());
+            }
+        }
+
+        let mut definitions = std::mem::take(&mut self.pending);
+        if self.config.reference_placement == ReferencePlacement::Section {
+            definitions.append(&mut remaining);
+        } else {
+            remaining.append(&mut definitions);
+            definitions = remaining;
+        }
+        self.write(result, definitions, offset);
+    }
+
+    /// Push `definitions` as an HTML block of `[label]: url` lines.
+    fn write(
+        &self,
+        result: &mut Vec<(Event<'_>, Range<usize>)>,
+        mut definitions: Vec<Definition>,
+        offset: usize,
+    ) {
+        if definitions.is_empty() {
+            return;
+        }
+        if self.config.sort_definitions {
+            definitions
+                .sort_by_cached_key(|definition| sort_key(&normalize(&definition.label), true));
+        }
+
+        result.push((Event::Start(Tag::HtmlBlock), offset..offset));
+        for definition in definitions {
+            let line = format!("{}\n", self.render(&definition));
+            result.push((Event::Html(line.into()), offset..offset));
+        }
+        result.push((Event::End(TagEnd::HtmlBlock), offset..offset));
+    }
+
+    /// Render a `[label]: url "title"` definition line.
+    fn render(&self, definition: &Definition) -> String {
+        let label = self.cased(&definition.label);
+        let url = if definition.url.is_empty() || definition.url.contains(' ') {
+            format!("<{}>", definition.url)
+        } else {
+            definition.url.clone()
+        };
+        if definition.title.is_empty() {
+            format!("[{label}]: {url}")
+        } else {
+            let title = definition.title.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("[{label}]: {url} \"{title}\"")
+        }
+    }
+}
+
+/// Reference definitions of `source` with their offsets, in document order.
+fn reference_definitions(source: &str) -> Vec<(Definition, usize)> {
+    let parser = Parser::new_ext(source, parser_options());
+    let mut definitions: Vec<_> = parser
+        .reference_definitions()
+        .iter()
+        .map(|(label, definition)| {
+            (
+                Definition {
+                    label: label.to_string(),
+                    url: definition.dest.to_string(),
+                    title: definition.title.as_deref().unwrap_or_default().to_string(),
+                },
+                definition.span.start,
+            )
+        })
+        .collect();
+    definitions.sort_by_key(|(_, start)| *start);
+    definitions
+}
+
+/// Warn about `[text][label]` and `[text][]` links without a definition.
+fn report_undefined(source: &str, diagnostics: &mut Diagnostics) {
+    let mut undefined = Vec::new();
+    let callback = |link: BrokenLink<'_>| -> Option<(CowStr<'_>, CowStr<'_>)> {
+        if matches!(link.link_type, LinkType::Reference | LinkType::Collapsed) {
+            undefined.push((link.span.start, link.reference.to_string()));
+        }
+        None
+    };
+    Parser::new_with_broken_link_callback(source, parser_options(), Some(callback)).for_each(drop);
+
+    for (start, label) in undefined {
+        diagnostics.warn(
+            DiagnosticKind::Link,
+            line_number(source, start),
+            format!("Undefined link reference [{label}]"),
+        );
+    }
+}
+
+/// Rewrite bare URLs and `<url>` autolinks in the configured style.
+///
+/// URLs in code, links and images are left alone. Angle brackets are only
+/// dropped where a renderer that links bare URLs finds the same URL.
+pub fn normalize_autolinks(
+    events: Vec<(Event<'_>, Range<usize>)>,
+    style: AutolinkStyle,
+) -> Vec<(Event<'_>, Range<usize>)> {
+    match style {
+        AutolinkStyle::Preserve => events,
+        AutolinkStyle::Angle => angle_autolinks(events),
+        AutolinkStyle::Bare => bare_autolinks(&events),
+    }
+}
+
+/// Wrap bare URLs in text in angle brackets.
+///
+/// Adjacent text events are joined first, since the parser may split a URL
+/// at characters like `_`.
+fn angle_autolinks(events: Vec<(Event<'_>, Range<usize>)>) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut result = Vec::with_capacity(events.len());
+    let mut text: Option<(String, Range<usize>)> = None;
+    let mut skip = 0_usize;
+
+    for (event, range) in events {
+        if let (Event::Text(t), 0) = (&event, skip) {
+            match &mut text {
+                Some((joined, joined_range)) => {
+                    joined.push_str(t);
+                    joined_range.end = range.end;
+                }
+                None => text = Some((t.to_string(), range)),
+            }
+            continue;
+        }
+        if let Some((joined, joined_range)) = text.take() {
+            push_linked_text(&mut result, &joined, &joined_range);
+        }
+
+        match &event {
+            Event::Start(Tag::Link { .. } | Tag::Image { .. } | Tag::CodeBlock(_)) => skip += 1,
+            Event::End(TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock) => skip -= 1,
+            Event::InlineHtml(html) if html_tag_name(html, "<a") => skip += 1,
+            Event::InlineHtml(html) if html_tag_name(html, "</a") => {
+                skip = skip.saturating_sub(1);
+            }
+            _ => {}
+        }
+        result.push((event, range));
+    }
+    if let Some((joined, joined_range)) = text {
+        push_linked_text(&mut result, &joined, &joined_range);
+    }
+
+    result
+}
+
+/// Check whether `html` is a tag starting with `open` (such as `<a`), matched
+/// case-insensitively and followed by the end of the tag name.
+fn html_tag_name(html: &str, open: &str) -> bool {
+    html.get(..open.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(open))
+        && html[open.len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c == '>' || c == '/' || c.is_ascii_whitespace())
+}
+
+/// Push `text` with every bare URL in it turned into an autolink.
+fn push_linked_text(result: &mut Vec<(Event<'_>, Range<usize>)>, text: &str, range: &Range<usize>) {
+    let mut rest = text;
+    while let Some(url) = find_url(rest) {
+        if url.start > 0 {
+            result.push((
+                Event::Text(rest[..url.start].to_string().into()),
+                range.clone(),
+            ));
+        }
+        let link = rest[url.clone()].to_string();
+        result.push((
+            Event::Start(Tag::Link {
+                link_type: LinkType::Autolink,
+                dest_url: link.clone().into(),
+                title: CowStr::Borrowed(""),
+                id: CowStr::Borrowed(""),
+            }),
+            range.clone(),
+        ));
+        result.push((Event::Text(link.into()), range.clone()));
+        result.push((Event::End(TagEnd::Link), range.clone()));
+        rest = &rest[url.end..];
+    }
+    if !rest.is_empty() {
+        result.push((Event::Text(rest.to_string().into()), range.clone()));
+    }
+}
+
+/// Find the first bare URL in `text`.
+///
+/// A URL starts with a scheme at a word boundary and runs to the next
+/// whitespace, without trailing punctuation or an unbalanced `)`.
+fn find_url(text: &str) -> Option<Range<usize>> {
+    let mut from = 0;
+    while let Some(found) = text[from..].find("http") {
+        let start = from + found;
+        from = start + 4;
+
+        let at_boundary = !text[..start]
+            .chars()
+            .last()
+            .is_some_and(char::is_alphanumeric);
+        let Some(scheme) = URL_SCHEMES
+            .iter()
+            .find(|scheme| text[start..].starts_with(*scheme))
+        else {
+            continue;
+        };
+        if !at_boundary {
+            continue;
+        }
+
+        let end = text[start..]
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .map_or(text.len(), |end| start + end);
+        let mut url = &text[start..end];
+        loop {
+            if let Some(trimmed) = url.strip_suffix(TRAILING_PUNCTUATION) {
+                url = trimmed;
+            } else if url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+                url = &url[..url.len() - 1];
+            } else {
+                break;
+            }
+        }
+
+        if url.len() > scheme.len() {
+            return Some(start..start + url.len());
+        }
+    }
+    None
+}
+
+/// Drop the angle brackets of autolinks that work as bare URLs.
+fn bare_autolinks<'a>(events: &[(Event<'a>, Range<usize>)]) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut result = Vec::with_capacity(events.len());
+    let mut index = 0;
+
+    while index < events.len() {
+        if let [(
+            Event::Start(Tag::Link {
+                link_type: LinkType::Autolink,
+                dest_url,
+                ..
+            }),
+            range,
+        ), (Event::Text(text), _), (Event::End(TagEnd::Link), _), ..] = &events[index..]
+        {
+            let before = index.checked_sub(1).map(|before| &events[before].0);
+            let after = events.get(index + 3).map(|(event, _)| event);
+            if text == dest_url && is_bare_url(dest_url, before, after) {
+                result.push((Event::InlineHtml(dest_url.clone()), range.clone()));
+                index += 3;
+                continue;
+            }
+        }
+        result.push(events[index].clone());
+        index += 1;
+    }
+
+    result
+}
+
+/// Whether `url` is recognized as the same link without angle brackets.
+fn is_bare_url(url: &str, before: Option<&Event<'_>>, after: Option<&Event<'_>>) -> bool {
+    let clean = URL_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+        && !url.ends_with(TRAILING_PUNCTUATION)
+        && !url.ends_with(')')
+        && !url.contains(['*', '_', '~', '`', '[', ']', '\\']);
+    let starts_word = match before {
+        Some(Event::Text(text)) => text.ends_with(|c: char| c.is_whitespace() || c == '('),
+        Some(Event::Code(_) | Event::InlineHtml(_) | Event::End(_)) => false,
+        _ => true,
+    };
+    let ends_word = match after {
+        Some(Event::Text(text)) => {
+            let mut chars = text.chars();
+            chars.next().is_some_and(|first| {
+                first.is_whitespace()
+                    || (TRAILING_PUNCTUATION.contains(&first) || first == ')')
+                        && chars.next().map_or(true, char::is_whitespace)
+            })
+        }
+        Some(Event::Code(_) | Event::InlineHtml(_) | Event::Start(_)) => false,
+        _ => true,
+    };
+    clean && starts_word && ends_word
+}
+
+/// The same link or image start event as an inline link.
+fn inline_event<'a>(event: &Event<'a>) -> Event<'a> {
+    let mut event = event.clone();
+    if let Event::Start(Tag::Link { link_type, id, .. } | Tag::Image { link_type, id, .. }) =
+        &mut event
+    {
+        *link_type = LinkType::Inline;
+        *id = CowStr::Borrowed("");
+    }
+    event
+}
+
+/// Normalize a label for matching: case-insensitive, whitespace collapsed.
+fn normalize(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Plain text of the link starting at the first event.
+fn link_text(events: &[(Event<'_>, Range<usize>)]) -> String {
+    let mut text = String::new();
+    let mut depth = 0;
+    for (event, _) in events {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Event::Text(t) | Event::Code(t) => text.push_str(t),
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::parse_markdown;
+
+    fn render(content: &str, config: &LinksConfig) -> (String, Diagnostics) {
+        let mut diagnostics = Diagnostics::new();
+        let events = convert_links(&parse_markdown(content), content, config, &mut diagnostics);
+        let text = events
+            .into_iter()
+            .filter_map(|(event, _)| match event {
+                Event::InlineHtml(html) | Event::Html(html) | Event::Text(html) => {
+                    Some(html.to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        (text, diagnostics)
+    }
+
+    #[test]
+    fn test_reference_links() {
+        let config = LinksConfig {
+            style: LinkStyle::Reference,
+            ..LinksConfig::default()
+        };
+        let content = "[a](https://a.com) [b][x] [c](https://b.com) [d](https://a.com \"T\")\n\n\
+                       [x]: https://b.com";
+        assert_eq!(
+            render(content, &config).0,
+            "[a][1] [b][x] [c][x] [d][2]\
+             [x]: https://b.com\n[1]: https://a.com\n[2]: https://a.com \"T\"\n"
+        );
+
+        let config = LinksConfig {
+            style: LinkStyle::Reference,
+            reference_labels: ReferenceLabels::Slug,
+            reference_placement: ReferencePlacement::Section,
+            ..LinksConfig::default()
+        };
+        let content =
+            "# A\n\n[Some Text](https://a.com) [Same](#a)\n\n# B\n\n[Some text](https://b.com)";
+        assert_eq!(
+            render(content, &config).0,
+            "A[Some Text][some-text] Same[some-text]: https://a.com\n\
+             B[Some text][some-text-1][some-text-1]: https://b.com\n"
+        );
+    }
+
+    #[test]
+    fn test_inline_links() {
+        let config = LinksConfig {
+            style: LinkStyle::Inline,
+            ..LinksConfig::default()
+        };
+        let content = "[a][x]\n\n[x]: https://x.com";
+        let events = convert_links(
+            &parse_markdown(content),
+            content,
+            &config,
+            &mut Diagnostics::new(),
+        );
+        assert!(events.iter().any(|(event, _)| matches!(
+            event,
+            Event::Start(Tag::Link {
+                link_type: LinkType::Inline,
+                ..
+            })
+        )));
+        assert!(!events
+            .iter()
+            .any(|(event, _)| matches!(event, Event::Html(_))));
+    }
+
+    #[test]
+    fn test_autolinks() {
+        let render = |content: &str, style| -> String {
+            normalize_autolinks(parse_markdown(content), style)
+                .into_iter()
+                .map(|(event, _)| match event {
+                    Event::Start(Tag::Link { .. }) => String::from("<"),
+                    Event::End(TagEnd::Link) => String::from(">"),
+                    Event::Text(text) | Event::InlineHtml(text) | Event::Code(text) => {
+                        text.to_string()
+                    }
+                    _ => String::new(),
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            render(
+                "See https://a.com/x_y, (http://b.com/(c)) and `https://code` [l](https://c.com)",
+                AutolinkStyle::Angle
+            ),
+            "See <https://a.com/x_y>, (<http://b.com/(c)>) and https://code <l>"
+        );
+        assert_eq!(
+            render(
+                "<a href=\"https://x.com\">https://x.com</a> <abbr>https://y.com</abbr>",
+                AutolinkStyle::Angle
+            ),
+            "<a href=\"https://x.com\">https://x.com</a> <abbr><https://y.com></abbr>"
+        );
+        assert_eq!(
+            render(
+                "See <https://a.com>. <https://b.com>x <https://c.com/a_b>",
+                AutolinkStyle::Bare
+            ),
+            "See https://a.com. <https://b.com>x <https://c.com/a_b>"
+        );
+    }
+
+    #[test]
+    fn test_reference_definitions() {
+        let content = "[b]: https://b.com\n\n[A][B] [c][] ![img][Unused2]\n\n\
+                       [Unused]: https://u.com\n[c]: https://c.com\n[unused2]: i.png\n\n[x][missing]";
+        let (text, diagnostics) = render(content, &LinksConfig::default());
+        assert_eq!(
+            text,
+            "[A][B] [c][] ![img][Unused2][x][missing]\
+             [b]: https://b.com\n[Unused]: https://u.com\n[c]: https://c.com\n[unused2]: i.png\n"
+        );
+        let messages: Vec<_> = diagnostics.messages().iter().map(|d| &d.message).collect();
+        assert_eq!(
+            messages,
+            [
+                "Undefined link reference [missing]",
+                "Unused link reference definition [Unused]"
+            ]
+        );
+
+        let config = LinksConfig {
+            sort_definitions: true,
+            label_case: LabelCase::Lower,
+            remove_unused: true,
+            ..LinksConfig::default()
+        };
+        let (text, _) = render(content, &config);
+        assert_eq!(
+            text,
+            "[A][b] [c][] ![img][unused2][x][missing]\
+             [b]: https://b.com\n[c]: https://c.com\n[unused2]: i.png\n"
+        );
+    }
+}
@@ -2,116 +2,727 @@
 //!
 //! Handles indentation and marker consistency for Markdown lists.
 
-use crate::config::ListConfig;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+use super::line_number;
+use crate::config::{
+    IndentStyle, ListConfig, ListSpacing, OrderedDelimiter, OrderedStyle, SortTasks,
+};
+use crate::diagnostics::{DiagnosticKind, Diagnostics};
+use crate::formatter::parse_markdown;
+
+/// List marker details of an item as written in the source.
+///
+/// The serializer writes every list with the same bullet, delimiter and
+/// sequential numbers, so the original markers are recorded before it runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceMarker {
+    /// Bullet (`-`, `*`, `+`) or delimiter (`.`, `)`) of the item's list.
+    pub list_marker: Option<char>,
+
+    /// The item's own number, for ordered lists.
+    pub number: Option<u64>,
+
+    /// Start number of the item's list, if ordered.
+    pub start: Option<u64>,
+
+    /// 1-based position of the item within its list.
+    pub position: usize,
+}
+
+/// A list item as found by the parser.
+struct ListItem {
+    /// 0-indexed line of the item's marker.
+    line: usize,
+
+    /// 0-indexed last line of the item.
+    end_line: usize,
+
+    /// Index of the item's list in document order.
+    list: usize,
+
+    /// Number of lists the item is nested in (0 for top-level lists).
+    depth: usize,
+
+    /// Start number of the item's list, if ordered.
+    start: Option<u64>,
+
+    /// 1-based position of the item within its list.
+    position: usize,
+
+    /// The item's marker in the source.
+    source: SourceMarker,
+
+    /// Checkbox state, for task list items.
+    task: Option<bool>,
+}
+
+/// An enclosing list item as rendered in the output.
+struct Parent {
+    indent: usize,
+    content_width: usize,
+    ordered: bool,
+
+    /// Content column of the item before re-indenting.
+    source_column: usize,
+
+    /// Last line of the item.
+    end_line: usize,
+}
 
 /// Format lists in markdown content.
 ///
 /// Normalizes indentation and list markers according to configuration.
-pub fn format_lists(content: &str, config: &ListConfig) -> String {
-    let lines: Vec<&str> = content.lines().collect();
+/// Nesting comes from the parsed list structure, so any input indentation
+/// that `CommonMark` accepts is re-indented correctly. `markers` holds the
+/// source marker of every list item, in document order.
+pub fn format_lists(content: &str, config: &ListConfig, markers: &[SourceMarker]) -> String {
+    let items = list_items(content, markers);
+
+    // Widest number of each ordered list, for right-aligned numbers
+    let mut widths: HashMap<usize, usize> = HashMap::new();
+    if config.align_numbers {
+        for item in &items {
+            if let Some(number) = item_number(item, config) {
+                let width = widths.entry(item.list).or_default();
+                *width = (*width).max(number.to_string().len());
+            }
+        }
+    }
+
+    let mut items = items.iter().peekable();
+    let mut parents: Vec<Parent> = Vec::new();
     let mut result = Vec::new();
-    let mut ordered_counter = 1;
-    let mut in_ordered_list = false;
-
-    for line in lines {
-        let trimmed = line.trim();
-
-        if is_unordered_list_item(trimmed) {
-            in_ordered_list = false;
-            let (level, content) = parse_list_item(line);
-            let indent = " ".repeat(level * config.indent_size);
-            result.push(format!("{indent}{} {content}", config.marker));
-        } else if is_ordered_list_item(trimmed) {
-            if !in_ordered_list {
-                ordered_counter = 1;
-                in_ordered_list = true;
+
+    for (index, line) in content.lines().enumerate() {
+        let item = items.next_if(|item| item.line == index);
+        // Items sharing a line (`- - a`) are left as written
+        while items.next_if(|item| item.line <= index).is_some() {}
+
+        let (Some(item), Some((marker, text))) = (item, split_marker(line)) else {
+            result.push(continuation_line(line, index, &parents));
+            continue;
+        };
+        let source_column = content_column(line, marker, text);
+        let text = item.task.map_or_else(
+            || text.to_string(),
+            |checked| normalize_checkbox(text, checked),
+        );
+
+        parents.truncate(item.depth);
+        let indent = parents
+            .last()
+            .map_or(0, |parent| parent.indent + nested_indent(parent, config));
+
+        let width = widths.get(&item.list).copied().unwrap_or(0);
+        let marker = render_marker(item, config, width);
+
+        let indentation = " ".repeat(indent);
+        if text.is_empty() {
+            result.push(format!("{indentation}{marker}"));
+        } else {
+            result.push(format!("{indentation}{marker} {text}"));
+        }
+
+        parents.push(Parent {
+            indent,
+            content_width: marker.len() + 1,
+            ordered: item.start.is_some(),
+            source_column,
+            end_line: item.end_line,
+        });
+    }
+
+    result.join("\n")
+}
+
+/// Re-indent a line that is not a list item marker line.
+///
+/// Lines inside a list item (continuation paragraphs, code, quotes) move to
+/// the item's new content column, keeping any extra indentation.
+fn continuation_line(line: &str, index: usize, parents: &[Parent]) -> String {
+    // The serializer pads blank lines inside list items with spaces
+    if line.trim().is_empty() {
+        return String::new();
+    }
+
+    let Some(parent) = parents.iter().rev().find(|parent| parent.end_line >= index) else {
+        return line.to_string();
+    };
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let column = parent.indent + parent.content_width;
+    let extra = indent.saturating_sub(parent.source_column);
+    format!("{}{}", " ".repeat(column + extra), &line[indent..])
+}
+
+/// Content column of a list item line, following `CommonMark`: one to four
+/// spaces after the marker belong to it, more start indented code.
+fn content_column(line: &str, marker: &str, text: &str) -> usize {
+    let trimmed = line.trim_start();
+    let after_marker = &trimmed[marker.len()..];
+    let gap = after_marker.len() - after_marker.trim_start().len();
+    let gap = if text.is_empty() || !(1..=4).contains(&gap) {
+        1
+    } else {
+        gap
+    };
+    line.len() - trimmed.len() + marker.len() + gap
+}
+
+/// Collect the source marker of each list item, in document order.
+pub fn collect_markers(events: &[(Event<'_>, Range<usize>)], source: &str) -> Vec<SourceMarker> {
+    let mut markers = Vec::new();
+    // Marker and start number of each open list, with the number of items seen
+    let mut lists: Vec<(Option<char>, Option<u64>, usize)> = Vec::new();
+
+    for (event, range) in events {
+        let marker = source.get(range.start..).unwrap_or_default();
+        match event {
+            Event::Start(Tag::List(None)) => lists.push((
+                marker
+                    .chars()
+                    .next()
+                    .filter(|c| matches!(c, '-' | '*' | '+')),
+                None,
+                0,
+            )),
+            Event::Start(Tag::List(Some(start))) => lists.push((
+                marker
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .chars()
+                    .next()
+                    .filter(|c| matches!(c, '.' | ')')),
+                Some(*start),
+                0,
+            )),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                let digits = marker.len()
+                    - marker
+                        .trim_start_matches(|c: char| c.is_ascii_digit())
+                        .len();
+                let (list_marker, start, count) =
+                    lists
+                        .last_mut()
+                        .map_or((None, None, 0), |(list_marker, start, count)| {
+                            *count += 1;
+                            (*list_marker, *start, *count)
+                        });
+                markers.push(SourceMarker {
+                    list_marker,
+                    number: marker[..digits].parse().ok(),
+                    start,
+                    position: count,
+                });
             }
+            _ => {}
+        }
+    }
 
-            let (level, content) = parse_list_item(line);
-            let indent = " ".repeat(level * config.indent_size);
+    markers
+}
 
-            if config.normalize_numbers {
-                result.push(format!("{indent}{ordered_counter}. {content}"));
-                ordered_counter += 1;
-            } else {
-                // Keep original numbering
-                result.push(line.to_string());
+/// Collect every list item with its line and nesting depth.
+fn list_items(content: &str, markers: &[SourceMarker]) -> Vec<ListItem> {
+    let mut items = Vec::new();
+    // Index and start number of each open list, with the number of items seen
+    let mut lists: Vec<(usize, Option<u64>, usize)> = Vec::new();
+    let mut list_count = 0;
+
+    for (event, range) in parse_markdown(content) {
+        match event {
+            Event::Start(Tag::List(start)) => {
+                lists.push((list_count, start, 0));
+                list_count += 1;
             }
-        } else {
-            // Reset ordered list counter on non-list lines
-            if !trimmed.is_empty() {
-                in_ordered_list = false;
-                ordered_counter = 1;
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                let depth = lists.len().saturating_sub(1);
+                if let Some((list, start, count)) = lists.last_mut() {
+                    *count += 1;
+                    items.push(ListItem {
+                        line: line_number(content, range.start) - 1,
+                        end_line: line_number(content, range.end.saturating_sub(1)) - 1,
+                        list: *list,
+                        depth,
+                        start: *start,
+                        position: *count,
+                        source: markers.get(items.len()).copied().unwrap_or_default(),
+                        task: None,
+                    });
+                }
             }
-            result.push(line.to_string());
+            // The checkbox always belongs to the item started last
+            Event::TaskListMarker(checked) => {
+                if let Some(item) = items.last_mut() {
+                    item.task = Some(checked);
+                }
+            }
+            _ => {}
         }
     }
 
-    result.join("\n")
+    items
 }
 
-/// Check if a line is an unordered list item.
-fn is_unordered_list_item(line: &str) -> bool {
-    let trimmed = line.trim();
-    (trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ "))
-        && !trimmed.starts_with("---")
+/// Number to write for an ordered list item.
+///
+/// Counts within the source list, since the serializer can merge adjacent
+/// lists that only differed in their delimiter.
+fn item_number(item: &ListItem, config: &ListConfig) -> Option<u64> {
+    let output_start = item.start?;
+    let (start, position) = item
+        .source
+        .start
+        .map_or((output_start, item.position), |start| {
+            (start, item.source.position)
+        });
+    let sequential = start + position as u64 - 1;
+    let style = if config.normalize_numbers {
+        config.ordered_style
+    } else {
+        OrderedStyle::Preserve
+    };
+
+    Some(match style {
+        OrderedStyle::Sequential => sequential,
+        OrderedStyle::One => start,
+        OrderedStyle::Preserve => item.source.number.unwrap_or(sequential),
+    })
 }
 
-/// Check if a line is an ordered list item.
-fn is_ordered_list_item(line: &str) -> bool {
-    let trimmed = line.trim();
-    trimmed.chars().next().is_some_and(|c| c.is_ascii_digit()) && trimmed.contains(". ")
+/// Render the marker of a list item, right-aligning numbers to `width`.
+fn render_marker(item: &ListItem, config: &ListConfig, width: usize) -> String {
+    let Some(number) = item_number(item, config) else {
+        return config.marker.for_depth(item.depth, item.source.list_marker);
+    };
+
+    let delimiter = match config.ordered_delimiter {
+        OrderedDelimiter::Period => '.',
+        OrderedDelimiter::Paren => ')',
+        OrderedDelimiter::Preserve => item.source.list_marker.unwrap_or('.'),
+    };
+    format!("{number:>width$}{delimiter}")
 }
 
-/// Parse list item to determine indentation level and content.
-fn parse_list_item(line: &str) -> (usize, String) {
-    let leading_spaces = line.len() - line.trim_start().len();
-    let level = leading_spaces / 2; // Assume 2-space indents in input
+/// Rewrite a task item's checkbox as `[ ]` or `[x]` followed by one space.
+fn normalize_checkbox(text: &str, checked: bool) -> String {
+    let rest = text
+        .strip_prefix('[')
+        .and_then(|text| text.split_once(']'))
+        .map_or(text, |(_, rest)| rest.trim_start());
+    let checkbox = if checked { "[x]" } else { "[ ]" };
 
-    let trimmed = line.trim();
-    // Remove marker and number
-    let content = if is_unordered_list_item(trimmed) {
-        trimmed[2..].to_string()
-    } else if is_ordered_list_item(trimmed) {
-        // Find the period and skip number
-        trimmed
-            .find(". ")
-            .map_or_else(|| trimmed.to_string(), |pos| trimmed[pos + 2..].to_string())
+    if rest.is_empty() {
+        checkbox.to_string()
     } else {
-        trimmed.to_string()
+        format!("{checkbox} {rest}")
+    }
+}
+
+/// Sort the lists that follow a `<!-- beautiful-md: sort-list -->` comment.
+///
+/// Options after `sort-list`: `case-insensitive` and `natural` (numbers
+/// compare by value, so `item2` sorts before `item10`). Items are sorted by
+/// their own text, ignoring emphasis and link syntax, and move together with
+/// their nested content.
+pub fn sort_marked_lists<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut events = events;
+
+    for index in 0..events.len() {
+        let Event::Html(html) = &events[index].0 else {
+            continue;
+        };
+        let Some(options) = sort_directive(html) else {
+            continue;
+        };
+        let line = line_number(source, events[index].1.start);
+
+        let mut case_insensitive = false;
+        let mut natural = false;
+        for option in options.split_whitespace() {
+            match option {
+                "case-insensitive" => case_insensitive = true,
+                "natural" => natural = true,
+                _ => diagnostics.warn(
+                    DiagnosticKind::List,
+                    line,
+                    format!("Unknown sort-list option '{option}'"),
+                ),
+            }
+        }
+
+        // The list must be the next block after the comment
+        let list = events[index + 1..]
+            .iter()
+            .position(|(event, _)| !matches!(event, Event::Html(_) | Event::End(TagEnd::HtmlBlock)))
+            .map(|pos| index + 1 + pos)
+            .filter(|&next| matches!(events[next].0, Event::Start(Tag::List(_))));
+        let Some(list) = list else {
+            diagnostics.warn(
+                DiagnosticKind::List,
+                line,
+                "sort-list directive is not followed by a list",
+            );
+            continue;
+        };
+
+        sort_list_items(&mut events, list, |item| {
+            let text = item_text(item);
+            let text = if case_insensitive {
+                text.to_lowercase()
+            } else {
+                text
+            };
+            sort_key(&text, natural)
+        });
+    }
+
+    events
+}
+
+/// Options of a `<!-- beautiful-md: sort-list ... -->` comment, if it is one.
+fn sort_directive(html: &str) -> Option<&str> {
+    html.trim()
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .trim()
+        .strip_prefix("beautiful-md:")?
+        .trim_start()
+        .strip_prefix("sort-list")
+        .filter(|options| options.is_empty() || options.starts_with(char::is_whitespace))
+}
+
+/// Plain text of a list item, without its nested lists.
+fn item_text(item: &[(Event<'_>, Range<usize>)]) -> String {
+    item.iter()
+        .take_while(|(event, _)| !matches!(event, Event::Start(Tag::List(_))))
+        .filter_map(|(event, _)| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Part of a sort key: runs of digits compare by value in natural order.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KeyPart {
+    /// Digit count without leading zeros, then the digits.
+    Number(usize, String),
+    Text(String),
+}
+
+/// Build the sort key of an item's text.
+pub fn sort_key(text: &str, natural: bool) -> Vec<KeyPart> {
+    if !natural {
+        return vec![KeyPart::Text(text.to_string())];
+    }
+
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let is_digit = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        if is_digit {
+            let digits = run.trim_start_matches('0');
+            parts.push(KeyPart::Number(digits.len(), digits.to_string()));
+        } else {
+            parts.push(KeyPart::Text(run.to_string()));
+        }
+        rest = tail;
+    }
+    parts
+}
+
+/// Move task items within each list according to `mode`.
+///
+/// The sort is stable, and items move together with their nested content.
+pub fn sort_tasks(
+    events: Vec<(Event<'_>, Range<usize>)>,
+    mode: SortTasks,
+) -> Vec<(Event<'_>, Range<usize>)> {
+    if mode == SortTasks::None {
+        return events;
+    }
+
+    let mut events = events;
+    for index in 0..events.len() {
+        if matches!(events[index].0, Event::Start(Tag::List(_))) {
+            sort_list_items(&mut events, index, |item| match (mode, task_state(item)) {
+                (SortTasks::UncheckedFirst, Some(false))
+                | (SortTasks::CheckedLast, None | Some(false)) => 0,
+                _ => 1,
+            });
+        }
+    }
+    events
+}
+
+/// Checkbox state of a list item, given the item's events.
+fn task_state(item: &[(Event<'_>, Range<usize>)]) -> Option<bool> {
+    item.iter()
+        .take_while(|(event, _)| !matches!(event, Event::Start(Tag::List(_))))
+        .find_map(|(event, _)| match event {
+            Event::TaskListMarker(checked) => Some(*checked),
+            _ => None,
+        })
+}
+
+/// Stably reorder the items of the list starting at event `start` by `key`.
+fn sort_list_items<K: Ord>(
+    events: &mut Vec<(Event<'_>, Range<usize>)>,
+    start: usize,
+    key: impl Fn(&[(Event<'_>, Range<usize>)]) -> K,
+) {
+    let spans = child_spans(events, start);
+    let mut sorted = spans.clone();
+    sorted.sort_by_key(|span| key(&events[span.clone()]));
+    if sorted == spans {
+        return;
+    }
+
+    let reordered: Vec<_> = sorted
+        .iter()
+        .flat_map(|span| events[span.clone()].to_vec())
+        .collect();
+    let (Some(first), Some(last)) = (spans.first(), spans.last()) else {
+        return;
     };
+    events.splice(first.start..last.end, reordered);
+}
+
+/// Event spans of the tagged children of the container starting at `start`.
+///
+/// For a list these are its items; for an item, its blocks.
+fn child_spans(events: &[(Event<'_>, Range<usize>)], start: usize) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut depth = 0;
+    let mut child_start = start + 1;
+
+    for (index, (event, _)) in events.iter().enumerate().skip(start + 1) {
+        match event {
+            Event::Start(_) => {
+                if depth == 0 {
+                    child_start = index;
+                }
+                depth += 1;
+            }
+            Event::End(_) if depth == 0 => break,
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    spans.push(child_start..index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
 
-    (level, content)
+    spans
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Make every list tight or loose.
+///
+/// Lists with an item that holds several paragraphs, or text after a
+/// nested block, cannot be made tight without changing their meaning. They
+/// stay loose and are reported instead.
+pub fn normalize_spacing<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    spacing: ListSpacing,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    match spacing {
+        ListSpacing::Preserve => events,
+        ListSpacing::Loose => make_loose(events),
+        ListSpacing::Tight => make_tight(events, source, diagnostics),
+    }
+}
 
-    #[test]
-    fn test_is_unordered_list_item() {
-        assert!(is_unordered_list_item("- Item"));
-        assert!(is_unordered_list_item("* Item"));
-        assert!(is_unordered_list_item("+ Item"));
-        assert!(!is_unordered_list_item("Not a list"));
+/// Wrap the bare text of tight list items in paragraphs.
+fn make_loose(events: Vec<(Event<'_>, Range<usize>)>) -> Vec<(Event<'_>, Range<usize>)> {
+    let mut result = Vec::with_capacity(events.len());
+    // Whether each open tag is a list item
+    let mut open_items: Vec<bool> = Vec::new();
+    let mut in_paragraph = false;
+
+    for (event, range) in events {
+        if open_items.last() == Some(&true) {
+            let inline = is_inline(&event);
+            if inline && !in_paragraph {
+                result.push((Event::Start(Tag::Paragraph), range.clone()));
+            } else if !inline && in_paragraph {
+                result.push((Event::End(TagEnd::Paragraph), range.clone()));
+            }
+            in_paragraph = inline;
+        }
+
+        match &event {
+            Event::Start(tag) => open_items.push(matches!(tag, Tag::Item)),
+            Event::End(_) => {
+                open_items.pop();
+            }
+            _ => {}
+        }
+        result.push((event, range));
     }
 
-    #[test]
-    fn test_is_ordered_list_item() {
-        assert!(is_ordered_list_item("1. Item"));
-        assert!(is_ordered_list_item("42. Item"));
-        assert!(!is_ordered_list_item("Not a list"));
+    result
+}
+
+/// Remove the paragraphs of loose list items where that keeps their meaning.
+fn make_tight<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut unwrap: HashSet<usize> = HashSet::new();
+
+    for (start, (event, _)) in events.iter().enumerate() {
+        if !matches!(event, Event::Start(Tag::List(_))) {
+            continue;
+        }
+
+        let mut paragraphs = Vec::new();
+        let mut tight = true;
+        for item in child_spans(&events, start) {
+            let blocks = child_spans(&events, item.start);
+            let item_paragraphs: Vec<&Range<usize>> = blocks
+                .iter()
+                .filter(|block| matches!(events[block.start].0, Event::Start(Tag::Paragraph)))
+                .collect();
+
+            // Only a single leading paragraph can lose its wrapper
+            let first_is_paragraph = blocks
+                .first()
+                .is_some_and(|block| item_paragraphs.first() == Some(&block));
+            if item_paragraphs.len() > 1 || (!item_paragraphs.is_empty() && !first_is_paragraph) {
+                tight = false;
+                diagnostics.warn(
+                    DiagnosticKind::List,
+                    line_number(source, events[item.start].1.start),
+                    "List item cannot be made tight without changing its meaning; list kept loose",
+                );
+            }
+            paragraphs.extend(item_paragraphs.into_iter().cloned());
+        }
+
+        if tight {
+            for paragraph in paragraphs {
+                unwrap.insert(paragraph.start);
+                unwrap.insert(paragraph.end - 1);
+            }
+        }
     }
 
-    #[test]
-    fn test_parse_list_item() {
-        let (level, content) = parse_list_item("- Item");
-        assert_eq!(level, 0);
-        assert_eq!(content, "Item");
+    events
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !unwrap.contains(index))
+        .map(|(_, event)| event)
+        .collect()
+}
 
-        let (level, content) = parse_list_item("  - Nested");
-        assert_eq!(level, 1);
-        assert_eq!(content, "Nested");
+/// Check if an event is inline content rather than a block.
+pub const fn is_inline(event: &Event<'_>) -> bool {
+    match event {
+        Event::Start(tag) => matches!(
+            tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link { .. } | Tag::Image { .. }
+        ),
+        Event::End(tag) => matches!(
+            tag,
+            TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Link
+                | TagEnd::Image
+        ),
+        Event::Text(_)
+        | Event::Code(_)
+        | Event::InlineHtml(_)
+        | Event::InlineMath(_)
+        | Event::DisplayMath(_)
+        | Event::SoftBreak
+        | Event::HardBreak
+        | Event::FootnoteReference(_)
+        | Event::TaskListMarker(_) => true,
+        Event::Html(_) | Event::Rule => false,
+    }
+}
+
+/// Indentation of a nested list relative to its parent item.
+///
+/// Stays between the parent's content column and the 3 extra spaces
+/// `CommonMark` allows, so re-indenting never changes the nesting.
+fn nested_indent(parent: &Parent, config: &ListConfig) -> usize {
+    if parent.ordered && config.indent_style == IndentStyle::MarkerWidth {
+        parent.content_width
+    } else {
+        config
+            .indent_size
+            .clamp(parent.content_width, parent.content_width + 3)
+    }
+}
+
+/// Split a list item line into its marker and content.
+///
+/// Returns `None` unless the line starts with a bullet (`-`, `*`, `+`) or
+/// an ordered marker (`1.`, `1)`) after optional indentation.
+fn split_marker(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    let marker_len = if trimmed.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = trimmed.len()
+            - trimmed
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        if digits == 0 || !trimmed[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        digits + 1
+    };
+
+    let rest = &trimmed[marker_len..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some((&trimmed[..marker_len], rest.trim_start()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BulletMarker;
+    use crate::diagnostics::Severity;
+
+    #[test]
+    fn test_split_marker() {
+        assert_eq!(split_marker("- Item"), Some(("-", "Item")));
+        assert_eq!(split_marker("    * Nested"), Some(("*", "Nested")));
+        assert_eq!(split_marker("42. Item"), Some(("42.", "Item")));
+        assert_eq!(split_marker("1) Item"), Some(("1)", "Item")));
+        assert_eq!(split_marker("-"), Some(("-", "")));
+        assert_eq!(split_marker("Not a list"), None);
+        assert_eq!(split_marker("**Bold**"), None);
+        assert_eq!(split_marker("> - Quoted"), None);
     }
 
     #[test]
@@ -119,27 +730,253 @@ mod tests {
         let input = "- Item 1\n* Item 2\n+ Item 3";
         let config = ListConfig {
             indent_size: 2,
-            marker: String::from("-"),
+            marker: BulletMarker::Single(String::from("-")),
             normalize_numbers: true,
+            ..Default::default()
         };
 
-        let result = format_lists(input, &config);
+        let result = format_lists(input, &config, &[]);
         assert!(result.contains("- Item 1"));
         assert!(result.contains("- Item 2"));
         assert!(result.contains("- Item 3"));
     }
 
+    #[test]
+    fn test_format_lists_nesting_from_structure() {
+        let config = ListConfig::default();
+
+        // Four-space input indentation is one level, not two
+        let input = "- A\n    - B\n        - C\n- D";
+        assert_eq!(
+            format_lists(input, &config, &[]),
+            "- A\n  - B\n    - C\n- D"
+        );
+
+        // Nested lists under ordered items need the marker's content column
+        let input = "1.  One\n    - Nested\n2.  Two";
+        assert_eq!(
+            format_lists(input, &config, &[]),
+            "1. One\n   - Nested\n2. Two"
+        );
+    }
+
+    #[test]
+    fn test_format_lists_marker_width_indent() {
+        let config = ListConfig {
+            indent_size: 4,
+            indent_style: IndentStyle::MarkerWidth,
+            ..Default::default()
+        };
+
+        let input = "1. One\n   1. Sub\n   2. Sub\n- Bullet\n  - Sub";
+        assert_eq!(
+            format_lists(input, &config, &[]),
+            "1. One\n   1. Sub\n   2. Sub\n- Bullet\n    - Sub"
+        );
+    }
+
+    #[test]
+    fn test_format_lists_markers_by_depth() {
+        let config = ListConfig {
+            marker: BulletMarker::PerLevel(vec![String::from("-"), String::from("*")]),
+            ..Default::default()
+        };
+
+        let input = "* A\n  * B\n    * C\n* D";
+        assert_eq!(
+            format_lists(input, &config, &[]),
+            "- A\n  * B\n    - C\n- D"
+        );
+    }
+
+    #[test]
+    fn test_format_lists_consistent_markers() {
+        let source = "+ A\n  - B\n+ C\n\n1. One\n\n* D";
+        let events: Vec<_> = parse_markdown(source);
+        let markers = collect_markers(&events, source);
+        let bullets: Vec<Option<char>> = markers.iter().map(|m| m.list_marker).collect();
+        assert_eq!(
+            bullets,
+            [Some('+'), Some('-'), Some('+'), Some('.'), Some('*')]
+        );
+
+        let config = ListConfig {
+            marker: BulletMarker::Single(String::from(BulletMarker::CONSISTENT)),
+            ..Default::default()
+        };
+        let input = "* A\n  * B\n* C\n\n1. One\n\n* D";
+        assert_eq!(
+            format_lists(input, &config, &markers),
+            "+ A\n  - B\n+ C\n\n1. One\n\n* D"
+        );
+    }
+
+    #[test]
+    fn test_format_lists_ordered_styles() {
+        let source = "5) Five\n5) Six\n9) Seven";
+        let events: Vec<_> = parse_markdown(source);
+        let markers = collect_markers(&events, source);
+        // What the serializer writes for the source above
+        let input = "5. Five\n6. Six\n7. Seven";
+
+        let config = ListConfig::default();
+        assert_eq!(
+            format_lists(input, &config, &markers),
+            "5. Five\n6. Six\n7. Seven"
+        );
+
+        let config = ListConfig {
+            ordered_style: OrderedStyle::One,
+            ordered_delimiter: OrderedDelimiter::Preserve,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_lists(input, &config, &markers),
+            "5) Five\n5) Six\n5) Seven"
+        );
+
+        let config = ListConfig {
+            ordered_style: OrderedStyle::Preserve,
+            ordered_delimiter: OrderedDelimiter::Paren,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_lists(input, &config, &markers),
+            "5) Five\n5) Six\n9) Seven"
+        );
+    }
+
+    #[test]
+    fn test_format_lists_align_numbers() {
+        let config = ListConfig {
+            align_numbers: true,
+            ..Default::default()
+        };
+        let input = "1. Item\n2. Item\n3. Item\n4. Item\n5. Item\n6. Item\n7. Item\n8. Item\n9. Item\n10. Item\n    - Sub";
+
+        let result = format_lists(input, &config, &[]);
+        assert!(result.starts_with(" 1. Item\n 2. Item"));
+        assert!(result.contains(" 9. Item\n10. Item\n    - Sub"));
+    }
+
+    #[test]
+    fn test_normalize_checkbox() {
+        assert_eq!(normalize_checkbox("[X]   Done", true), "[x] Done");
+        assert_eq!(normalize_checkbox("[ ]", false), "[ ]");
+    }
+
+    #[test]
+    fn test_sort_tasks() {
+        let source = "- [x] Done\n- Note\n- [ ] Todo\n  - [x] Sub done\n  - [ ] Sub todo\n";
+        let tasks = |mode| {
+            let events = sort_tasks(parse_markdown(source), mode);
+            let mut buf = String::new();
+            pulldown_cmark_to_cmark::cmark(events.iter().map(|(event, _)| event), &mut buf)
+                .unwrap();
+            buf
+        };
+
+        assert_eq!(
+            tasks(SortTasks::UncheckedFirst),
+            "* [ ] Todo\n  * [ ] Sub todo\n  * [x] Sub done\n* [x] Done\n* Note"
+        );
+        assert_eq!(
+            tasks(SortTasks::CheckedLast),
+            "* Note\n* [ ] Todo\n  * [ ] Sub todo\n  * [x] Sub done\n* [x] Done"
+        );
+    }
+
+    fn spaced(source: &str, spacing: ListSpacing) -> (String, Diagnostics) {
+        let mut diagnostics = Diagnostics::new();
+        let events = normalize_spacing(parse_markdown(source), source, spacing, &mut diagnostics);
+        let mut buf = String::new();
+        pulldown_cmark_to_cmark::cmark(events.iter().map(|(event, _)| event), &mut buf).unwrap();
+        (buf, diagnostics)
+    }
+
+    #[test]
+    fn test_list_spacing() {
+        let (result, _) = spaced("- [ ] a\n- b\n  - c", ListSpacing::Loose);
+        assert_eq!(result, "* [ ] a\n\n* b\n  \n  * c");
+
+        let (result, diagnostics) = spaced("- a\n\n- b\n  - c\n\n- d", ListSpacing::Tight);
+        assert_eq!(result, "* a\n* b\n  * c\n* d");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_list_spacing_keeps_multi_paragraph_items_loose() {
+        let source = "- a\n\n  more\n- b";
+        let (result, diagnostics) = spaced(source, ListSpacing::Tight);
+
+        assert_eq!(result, spaced(source, ListSpacing::Preserve).0);
+        assert_eq!(diagnostics.by_severity(Severity::Warning).len(), 1);
+        assert_eq!(diagnostics.messages()[0].line, 1);
+    }
+
+    #[test]
+    fn test_format_lists_continuation_lines() {
+        let config = ListConfig::default();
+        let input = "*   Item\n\n    More text\n\n    <!--BEAUTIFUL_MD_CODE_BLOCK_0-->\n\n    > Quote\n\n      - Nested\n\n        Nested text\n\nAfter";
+
+        assert_eq!(
+            format_lists(input, &config, &[]),
+            "- Item\n\n  More text\n\n  <!--BEAUTIFUL_MD_CODE_BLOCK_0-->\n\n  > Quote\n\n  - Nested\n\n    Nested text\n\nAfter"
+        );
+    }
+
+    fn sorted_list(source: &str) -> (String, Diagnostics) {
+        let mut diagnostics = Diagnostics::new();
+        let events = sort_marked_lists(parse_markdown(source), source, &mut diagnostics);
+        let mut buf = String::new();
+        pulldown_cmark_to_cmark::cmark(events.iter().map(|(event, _)| event), &mut buf).unwrap();
+        (buf, diagnostics)
+    }
+
+    #[test]
+    fn test_sort_marked_lists() {
+        let (result, _) = sorted_list(
+            "<!-- beautiful-md: sort-list -->\n- *beta*\n- [Alpha](#a)\n  - z\n  - y\n- alpha\n\nText\n\n- b\n- a",
+        );
+        assert!(result.contains("* [Alpha](#a)\n  * z\n  * y\n* alpha\n* *beta*"));
+        // Only the list right after the directive is sorted
+        assert!(result.ends_with("* b\n* a"));
+
+        let (result, _) = sorted_list(
+            "<!-- beautiful-md: sort-list case-insensitive natural -->\n- item10\n- Item2\n- item1",
+        );
+        assert!(result.ends_with("* item1\n* Item2\n* item10"));
+    }
+
+    #[test]
+    fn test_sort_marked_lists_diagnostics() {
+        let (_, diagnostics) = sorted_list("<!-- beautiful-md: sort-list reverse -->\n\nText");
+        let messages: Vec<&str> = diagnostics
+            .messages()
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "Unknown sort-list option 'reverse'",
+                "sort-list directive is not followed by a list"
+            ]
+        );
+    }
+
     #[test]
     fn test_bold_before_list() {
         // This is what cmark() produces
         let input = "# Test\n\n**Table of Contents:**\n\n* Tables with various structures\n* Headings at different levels";
         let config = ListConfig {
             indent_size: 2,
-            marker: String::from("-"),
+            marker: BulletMarker::Single(String::from("-")),
             normalize_numbers: true,
+            ..Default::default()
         };
 
-        let result = format_lists(input, &config);
+        let result = format_lists(input, &config, &[]);
         eprintln!("INPUT:\n{input}");
         eprintln!("\nOUTPUT:\n{result}");
 
@@ -4,30 +4,129 @@
 //! - Tables
 //! - Headings
 //! - Lists
+//! - Emphasis
+//! - Links
+//! - Images
 //! - Code blocks
 
 mod code;
+mod emphasis;
 mod heading;
+mod image;
+mod links;
 mod list;
+mod paragraph;
 mod table;
+mod toc;
 
-use heading::format_headings;
-use list::format_lists;
+use std::ops::Range;
+
+use pulldown_cmark::Event;
+
+use emphasis::protect_intraword_emphasis;
+use heading::{
+    apply_heading_case, check_heading_hierarchy, format_headings, manage_heading_ids,
+    number_headings, process_heading_events, HeadingInfo,
+};
+pub use image::img_sources;
+use image::lint_images;
+use links::{convert_links, normalize_autolinks};
+use list::{
+    collect_markers, format_lists, normalize_spacing, sort_marked_lists, sort_tasks, SourceMarker,
+};
+use paragraph::wrap_paragraphs;
 use table::format_tables;
+pub use table::{is_delimiter_row, split_cells};
+use toc::insert_toc;
 
 use crate::config::Config;
+use crate::diagnostics::Diagnostics;
+
+/// Start of the placeholder that stands in for an extracted code block.
+const CODE_BLOCK_PLACEHOLDER: &str = "<!--BEAUTIFUL_MD_CODE_BLOCK_";
+
+/// A fenced code block replaced by a placeholder before formatting.
+#[derive(Debug)]
+pub struct CodeBlock {
+    /// Info string after the opening fence.
+    lang: String,
+
+    /// Code lines, without the fence indentation.
+    code: String,
+
+    /// Source lines the block spanned, fences included.
+    lines: usize,
+}
+
+/// Information gathered from parser events for the text formatters.
+#[derive(Debug, Default)]
+pub struct DocumentInfo {
+    /// Top-level headings in document order.
+    headings: Vec<HeadingInfo>,
+
+    /// Source marker of each list item, in document order.
+    list_markers: Vec<SourceMarker>,
+}
+
+/// Apply event-level formatters to parsed markdown.
+///
+/// Rewrites the events before they are serialized and collects the
+/// information the text formatters need afterwards.
+pub fn transform_events<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    config: &Config,
+    diagnostics: &mut Diagnostics,
+) -> (Vec<Event<'a>>, DocumentInfo) {
+    let events = check_heading_hierarchy(events, source, &config.headings, diagnostics);
+    let (events, headings) = process_heading_events(events, source, &config.headings);
+    let events = apply_heading_case(events, source, &config.headings, diagnostics);
+    let events = manage_heading_ids(events, source, &config.headings, diagnostics);
+    let events = number_headings(events, &config.headings.numbering);
+    let events = insert_toc(
+        events,
+        source,
+        &config.toc,
+        config.headings.slug_style,
+        diagnostics,
+    );
+
+    let events = sort_marked_lists(events, source, diagnostics);
+    let events = sort_tasks(events, config.lists.sort_tasks);
+    let events = normalize_spacing(events, source, config.lists.spacing, diagnostics);
+    let list_markers = collect_markers(&events, source);
+    let events = lint_images(&events, source, &config.images, diagnostics);
+    let events = normalize_autolinks(events, config.links.autolink);
+    let events = protect_intraword_emphasis(events, &config.emphasis);
+    let events = convert_links(&events, source, &config.links, diagnostics);
+    let events = wrap_paragraphs(events, &config.paragraphs);
+
+    let events = events.into_iter().map(|(event, _)| event).collect();
+    (
+        events,
+        DocumentInfo {
+            headings,
+            list_markers,
+        },
+    )
+}
+
+/// Get the 1-indexed line number of a byte offset in `source`.
+fn line_number(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
 
 /// Apply all formatters to markdown content.
 ///
 /// This function orchestrates the application of all individual formatters
 /// in the correct order to avoid conflicts.
-pub fn apply_all(content: &str, config: &Config) -> String {
+pub fn apply_all(content: &str, config: &Config, info: &DocumentInfo) -> String {
     let mut result = content.to_string();
 
     // Apply formatters (code blocks are already protected at this point)
     result = format_tables(&result, &config.tables);
-    result = format_headings(&result, &config.headings);
-    result = format_lists(&result, &config.lists);
+    result = format_headings(&result, &config.headings, &info.headings);
+    result = format_lists(&result, &config.lists, &info.list_markers);
 
     result
 }
@@ -36,13 +135,19 @@ pub fn apply_all(content: &str, config: &Config) -> String {
 ///
 /// This preserves code blocks completely verbatim, preventing any markdown processing.
 /// Returns the content with placeholders and a vec of extracted code blocks.
-pub fn extract_code_blocks_early(content: &str) -> (String, Vec<(String, String)>) {
+///
+/// The placeholder keeps the indentation of the opening fence, so code inside
+/// list items stays inside the item, and that indentation is removed from the
+/// code lines. Restoring re-applies whatever indentation the placeholder has
+/// after formatting.
+pub fn extract_code_blocks_early(content: &str) -> (String, Vec<CodeBlock>) {
     let lines: Vec<&str> = content.lines().collect();
     let mut result = Vec::new();
     let mut code_blocks = Vec::new();
     let mut in_code_block = false;
     let mut current_block = Vec::new();
     let mut current_lang = String::new();
+    let mut current_indent = 0;
 
     for line in lines {
         let trimmed = line.trim();
@@ -50,9 +155,14 @@ pub fn extract_code_blocks_early(content: &str) -> (String, Vec<(String, String)
         if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
             if in_code_block {
                 // End of code block
-                code_blocks.push((current_lang.clone(), current_block.join("\n")));
+                code_blocks.push(CodeBlock {
+                    lang: current_lang.clone(),
+                    code: current_block.join("\n"),
+                    lines: current_block.len() + 2,
+                });
                 result.push(format!(
-                    "<!--BEAUTIFUL_MD_CODE_BLOCK_{}-->",
+                    "{}<!--BEAUTIFUL_MD_CODE_BLOCK_{}-->",
+                    " ".repeat(current_indent),
                     code_blocks.len() - 1
                 ));
                 current_block.clear();
@@ -60,6 +170,7 @@ pub fn extract_code_blocks_early(content: &str) -> (String, Vec<(String, String)
             } else {
                 // Start of code block
                 in_code_block = true;
+                current_indent = line.len() - line.trim_start_matches(' ').len();
                 current_lang = if trimmed.len() > 3 {
                     trimmed[3..].trim().to_string()
                 } else {
@@ -67,7 +178,8 @@ pub fn extract_code_blocks_early(content: &str) -> (String, Vec<(String, String)
                 };
             }
         } else if in_code_block {
-            current_block.push(line);
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            current_block.push(&line[indent.min(current_indent)..]);
         } else {
             result.push(line.to_string());
         }
@@ -75,9 +187,14 @@ pub fn extract_code_blocks_early(content: &str) -> (String, Vec<(String, String)
 
     // Handle unclosed code block
     if in_code_block {
-        code_blocks.push((current_lang, current_block.join("\n")));
+        code_blocks.push(CodeBlock {
+            lang: current_lang,
+            code: current_block.join("\n"),
+            lines: current_block.len() + 1,
+        });
         result.push(format!(
-            "<!--BEAUTIFUL_MD_CODE_BLOCK_{}-->",
+            "{}<!--BEAUTIFUL_MD_CODE_BLOCK_{}-->",
+            " ".repeat(current_indent),
             code_blocks.len() - 1
         ));
     }
@@ -88,37 +205,79 @@ pub fn extract_code_blocks_early(content: &str) -> (String, Vec<(String, String)
 /// Restore code blocks into content early (after all formatting), replacing placeholders.
 ///
 /// Applies the configured fence style while preserving code block content verbatim.
+/// Every line of the block gets the text in front of its placeholder, such as
+/// list item indentation.
 pub fn restore_code_blocks_early(
     content: &str,
-    code_blocks: &[(String, String)],
+    code_blocks: &[CodeBlock],
     config: &Config,
 ) -> String {
     let fence = &config.code.fence_style;
-    let mut result = content.to_string();
+    let mut result = Vec::new();
 
-    for (i, (lang, block_content)) in code_blocks.iter().enumerate() {
-        let placeholder = format!("<!--BEAUTIFUL_MD_CODE_BLOCK_{i}-->");
-        let code_block = if lang.is_empty() {
-            format!("{fence}\n{block_content}\n{fence}")
-        } else {
-            format!("{fence}{lang}\n{block_content}\n{fence}")
+    for line in content.split('\n') {
+        let block = placeholder(line).and_then(|(pos, index)| {
+            let block = code_blocks.get(index)?;
+            Some((&line[..pos], &block.lang, &block.code))
+        });
+        let Some((prefix, lang, block_content)) = block else {
+            result.push(line.to_string());
+            continue;
         };
 
-        result = result.replace(&placeholder, &code_block);
+        result.push(format!("{prefix}{fence}{lang}"));
+        for code_line in block_content.split('\n') {
+            if code_line.is_empty() {
+                result.push(prefix.trim_end().to_string());
+            } else {
+                result.push(format!("{prefix}{code_line}"));
+            }
+        }
+        result.push(format!("{prefix}{fence}"));
     }
 
-    result
+    result.join("\n")
+}
+
+/// Map a line of `content`, where code blocks are placeholders, back to its
+/// line in the document the blocks were extracted from.
+pub fn original_line(content: &str, code_blocks: &[CodeBlock], line: usize) -> usize {
+    let hidden: usize = content
+        .lines()
+        .take(line.saturating_sub(1))
+        .filter_map(|line| code_blocks.get(placeholder(line)?.1))
+        .map(|block| block.lines - 1)
+        .sum();
+    line + hidden
+}
+
+/// Position and block index of a code block placeholder in `line`.
+fn placeholder(line: &str) -> Option<(usize, usize)> {
+    let pos = line.find(CODE_BLOCK_PLACEHOLDER)?;
+    let index = line[pos + CODE_BLOCK_PLACEHOLDER.len()..]
+        .strip_suffix("-->")?
+        .parse()
+        .ok()?;
+    Some((pos, index))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_original_line() {
+        let (content, blocks) = extract_code_blocks_early("a\n```\n1\n2\n```\nb\n~~~\nc");
+        assert_eq!(original_line(&content, &blocks, 1), 1);
+        assert_eq!(original_line(&content, &blocks, 3), 6);
+        assert_eq!(original_line(&content, &blocks, 4), 7);
+    }
+
     #[test]
     fn test_apply_all() {
         let content = "# Test\n\nSome text.";
         let config = Config::default();
-        let result = apply_all(content, &config);
+        let result = apply_all(content, &config, &DocumentInfo::default());
         assert!(!result.is_empty());
     }
 }
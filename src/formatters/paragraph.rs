@@ -0,0 +1,666 @@
+//! Paragraph formatting module.
+//!
+//! Reflows the text of paragraphs, including paragraphs in block quotes,
+//! list items and footnotes, to the configured line width or to one
+//! sentence per line.
+
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+
+use super::list::is_inline;
+use crate::config::{ParagraphConfig, WrapMode};
+
+/// Words ending in `.` that do not end a sentence, without the final dot.
+const ABBREVIATIONS: [&str; 24] = [
+    "e.g", "i.e", "etc", "vs", "cf", "al", "approx", "dr", "mr", "mrs", "ms", "prof", "sr", "jr",
+    "st", "no", "vol", "fig", "eq", "p", "pp", "inc", "ltd", "co",
+];
+
+/// Tags that start an HTML block able to interrupt a paragraph.
+const HTML_BLOCK_TAGS: [&str; 62] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
+
+/// Tags whose HTML block keeps its content raw until the closing tag.
+const RAW_HTML_TAGS: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+/// Characters that may follow the punctuation ending a sentence.
+const CLOSERS: [char; 7] = ['"', '\'', ')', ']', '*', '\u{201d}', '\u{2019}'];
+
+/// Characters that may precede the first letter of a sentence.
+const OPENERS: [char; 6] = ['"', '\'', '(', '[', '\u{201c}', '\u{2018}'];
+
+/// Part of a word, written back as events.
+enum Part<'a> {
+    /// Text, `joined` when it continues the text event before it.
+    ///
+    /// Escapes split text into separate events, and the writer only escapes
+    /// the first character of each, so text is only joined where the source
+    /// had one event or where wrapping put a space.
+    Text {
+        text: String,
+        joined: bool,
+    },
+    Event(Event<'a>),
+}
+
+impl Part<'_> {
+    fn text(&self) -> Option<&str> {
+        match self {
+            Part::Text { text, .. } => Some(text),
+            Part::Event(_) => None,
+        }
+    }
+}
+
+/// Inline content that must stay on one line.
+///
+/// Code spans, links and images are a single word however many spaces
+/// they contain, so breaking lines never changes how they parse.
+#[derive(Default)]
+struct Word<'a> {
+    parts: Vec<Part<'a>>,
+    width: usize,
+}
+
+impl<'a> Word<'a> {
+    fn push_text(&mut self, text: &str, joined: bool) {
+        self.width += text.chars().count();
+        match self.parts.last_mut() {
+            Some(Part::Text { text: last, .. }) if joined => last.push_str(text),
+            _ if !text.is_empty() => self.parts.push(Part::Text {
+                text: text.to_string(),
+                joined,
+            }),
+            _ => {}
+        }
+    }
+
+    fn push_event(&mut self, event: Event<'a>, width: usize) {
+        self.width += width;
+        self.parts.push(Part::Event(event));
+    }
+
+    /// Whether starting a line with this word would change the document.
+    ///
+    /// A line starting with `-`, `+`, `*` or `1.` followed by a space is a
+    /// list item, `#` a heading, `>` a block quote, three backticks or tildes
+    /// a code fence, a line of only `=` or `-` a setext underline, and one of
+    /// `*`, `_` or `-` a thematic break. Block-level HTML tags and comments
+    /// start an HTML block.
+    fn breaks_structure(&self) -> bool {
+        let text: String = match self.parts.first() {
+            Some(Part::Text { .. }) => self.parts.iter().map_while(Part::text).collect(),
+            Some(Part::Event(Event::InlineHtml(html))) => return starts_html_block(html),
+            // Code spans containing `` are written with three or more backticks
+            Some(Part::Event(Event::Code(code))) => return code.contains("``"),
+            _ => return false,
+        };
+        let whole = self.parts.iter().all(|part| part.text().is_some());
+        let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+        let rule = |marker| !text.is_empty() && text.bytes().all(|b| b == marker);
+
+        matches!(text.as_str(), "-" | "+" | "*" | "_")
+            || (digits > 0 && digits < 10 && matches!(&text[digits..], "." | ")"))
+            || (text.len() <= 6 && text.bytes().all(|b| b == b'#'))
+            || text.starts_with('>')
+            || text.starts_with("~~~")
+            || text.starts_with("```")
+            || (whole && (rule(b'=') || rule(b'-') || rule(b'*') || rule(b'_')))
+    }
+
+    /// Whether this word ends a sentence, like `done.` or `"Why?"`.
+    ///
+    /// Abbreviations (`e.g.`, `Dr.`), initials (`J.`, `U.S.`) and ellipses
+    /// do not. Punctuation inside code spans and links never counts.
+    fn ends_sentence(&self) -> bool {
+        let Some(end) = self.parts.iter().rposition(|part| {
+            !matches!(
+                part,
+                Part::Event(Event::End(
+                    TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough
+                ))
+            )
+        }) else {
+            return false;
+        };
+        let start = self.parts[..=end]
+            .iter()
+            .rposition(|part| part.text().is_none())
+            .map_or(0, |event| event + 1);
+        if start > end {
+            return false;
+        }
+
+        let text: String = self.parts[start..=end]
+            .iter()
+            .filter_map(Part::text)
+            .collect();
+        let text = text.trim_end_matches(CLOSERS);
+        if text.ends_with(['!', '?']) {
+            return true;
+        }
+        let Some(stem) = text.strip_suffix('.') else {
+            return false;
+        };
+        let stem = stem.trim_start_matches(OPENERS);
+        if stem.is_empty() {
+            // Punctuation right after a code span or link
+            return start > 0;
+        }
+        let initials = stem.split('.').all(|letters| {
+            letters.chars().count() == 1 && letters.chars().all(char::is_alphabetic)
+        });
+
+        !stem.ends_with('.') && !initials && !ABBREVIATIONS.contains(&stem.to_lowercase().as_str())
+    }
+
+    /// Whether this word starts with a lowercase letter.
+    fn starts_lowercase(&self) -> bool {
+        self.parts.first().and_then(Part::text).is_some_and(|text| {
+            text.trim_start_matches(OPENERS)
+                .starts_with(char::is_lowercase)
+        })
+    }
+
+    /// Whether this word is only HTML tags, like `<br/>`.
+    fn is_html(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part, Part::Event(Event::InlineHtml(_))))
+    }
+
+    /// The word roughly as it is written, enough to spot `[label]:`.
+    fn written(&self) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text { text, .. } => text.as_str(),
+                Part::Event(Event::InlineHtml(html)) => html.as_ref(),
+                Part::Event(Event::Start(Tag::Link { .. } | Tag::Image { .. })) => "[",
+                Part::Event(Event::End(TagEnd::Link | TagEnd::Image)) => "]",
+                Part::Event(_) => "",
+            })
+            .collect()
+    }
+}
+
+/// Inline content of a paragraph, split at the places a line may break.
+enum Token<'a> {
+    Word(Word<'a>),
+    Space,
+    HardBreak(Event<'a>),
+}
+
+/// Break paragraph text into lines according to `config.wrap`.
+///
+/// `always` fills lines up to `line_width` columns, counting the
+/// indentation of block quotes and list items. `never` joins each
+/// paragraph into one line, and `sentence` puts each sentence on its own
+/// line. Headings, tables and code are left alone.
+pub fn wrap_paragraphs<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    config: &ParagraphConfig,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let width = match config.wrap {
+        WrapMode::Preserve => return events,
+        WrapMode::Always => config.line_width,
+        WrapMode::Never | WrapMode::Sentence => usize::MAX,
+    };
+    let sentences = config.wrap == WrapMode::Sentence;
+
+    let mut result = Vec::with_capacity(events.len());
+    let mut run = Vec::new();
+    // Indentation and wrappability of each open block
+    let mut blocks: Vec<(usize, bool)> = Vec::new();
+    // Next number of each open list, `None` for bullet lists
+    let mut lists: Vec<Option<u64>> = Vec::new();
+
+    for (event, range) in events {
+        let in_text = blocks.last().is_some_and(|&(_, wrappable)| wrappable);
+        if in_text && is_inline(&event) {
+            run.push((event, range));
+            continue;
+        }
+        if !run.is_empty() {
+            let indent = blocks.iter().map(|&(indent, _)| indent).sum::<usize>();
+            result.extend(wrap_run(
+                std::mem::take(&mut run),
+                width.saturating_sub(indent),
+                sentences,
+            ));
+        }
+
+        match &event {
+            Event::Start(Tag::List(start)) => {
+                lists.push(*start);
+                blocks.push((0, false));
+            }
+            Event::Start(Tag::Item) => {
+                let indent = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        (*number - 1).to_string().len() + 2
+                    }
+                    _ => 2,
+                };
+                blocks.push((indent, true));
+            }
+            Event::Start(Tag::BlockQuote(_)) => blocks.push((2, false)),
+            Event::Start(Tag::FootnoteDefinition(_)) => blocks.push((4, false)),
+            Event::Start(Tag::Paragraph) => blocks.push((0, true)),
+            Event::Start(_) if !is_inline(&event) => blocks.push((0, false)),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                blocks.pop();
+            }
+            Event::End(_) if !is_inline(&event) => {
+                blocks.pop();
+            }
+            _ => {}
+        }
+        result.push((event, range));
+    }
+
+    if !run.is_empty() {
+        result.extend(wrap_run(run, width, sentences));
+    }
+    result
+}
+
+/// Reflow one run of inline events to `width` columns, breaking after
+/// every sentence when `sentences` is set.
+fn wrap_run<'a>(
+    run: Vec<(Event<'a>, Range<usize>)>,
+    width: usize,
+    sentences: bool,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let range = run[0].1.clone();
+    let tokens = tokenize(run.into_iter().map(|(event, _)| event));
+    let definition = looks_like_definition(&tokens);
+
+    let mut parts = Vec::new();
+    let mut column = 0;
+    let mut space = false;
+    let mut sentence_end = false;
+    // Words on the first line, which must not become a block of its own
+    let mut first_line = Some(0);
+    // Whether the first word is an HTML tag, a block on a line by itself
+    let mut html_start = false;
+    for token in tokens {
+        match token {
+            Token::Space => space = column > 0,
+            Token::HardBreak(event) => {
+                parts.push(Part::Event(event));
+                column = 0;
+                space = false;
+                first_line = None;
+            }
+            Token::Word(word) => {
+                if space {
+                    let full = column + 1 + word.width > width;
+                    let next_sentence = sentences && sentence_end && !word.starts_lowercase();
+                    let keep_first_line =
+                        first_line.is_some_and(|words| definition || (words == 1 && html_start));
+                    if (full || next_sentence) && !word.breaks_structure() && !keep_first_line {
+                        parts.push(Part::Event(Event::SoftBreak));
+                        column = 0;
+                        first_line = None;
+                    } else {
+                        parts.push(Part::Text {
+                            text: String::from(" "),
+                            joined: true,
+                        });
+                        column += 1;
+                    }
+                    space = false;
+                }
+                column += word.width;
+                sentence_end = word.ends_sentence();
+                if first_line == Some(0) {
+                    html_start = word.is_html();
+                }
+                first_line = first_line.map(|words| words + 1);
+                parts.extend(word.parts);
+            }
+        }
+    }
+
+    // Join text back into the events it came from, so only the new line
+    // starts get escaped
+    let mut result: Vec<(Event<'a>, Range<usize>)> = Vec::with_capacity(parts.len());
+    for part in parts {
+        match part {
+            Part::Text { text, joined } => {
+                if let (true, Some((Event::Text(last), _))) = (joined, result.last_mut()) {
+                    *last = CowStr::from(format!("{last}{text}"));
+                } else {
+                    result.push((Event::Text(text.into()), range.clone()));
+                }
+            }
+            Part::Event(event) => result.push((event, range.clone())),
+        }
+    }
+    result
+}
+
+/// Whether the text starts like a link reference definition, `[label]:`.
+///
+/// Breaking its first line could turn that line into a definition, so
+/// such paragraphs keep their first line whole.
+fn looks_like_definition(tokens: &[Token<'_>]) -> bool {
+    let mut written = String::new();
+    for token in tokens {
+        match token {
+            Token::Word(word) => written.push_str(&word.written()),
+            Token::Space => written.push(' '),
+            Token::HardBreak(_) => break,
+        }
+        if !written.starts_with('[') {
+            return false;
+        }
+    }
+    // The label may hold escaped brackets, so any `]:` counts
+    written.contains("]:")
+}
+
+/// Split inline events into words, spaces and hard breaks.
+fn tokenize<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    let mut word = Word::default();
+    // Nesting of links and images, whose content is never broken
+    let mut depth = 0_usize;
+
+    let finish = |word: &mut Word<'a>, tokens: &mut Vec<Token<'a>>| {
+        if !word.parts.is_empty() {
+            tokens.push(Token::Word(std::mem::take(word)));
+        }
+    };
+
+    for event in events {
+        match event {
+            Event::Text(text) if depth == 0 => {
+                for (i, piece) in text.split(' ').enumerate() {
+                    if i > 0 {
+                        finish(&mut word, &mut tokens);
+                        tokens.push(Token::Space);
+                    }
+                    word.push_text(piece, i > 0);
+                }
+            }
+            Event::Text(text) => word.push_text(&text, false),
+            Event::SoftBreak if depth == 0 => {
+                finish(&mut word, &mut tokens);
+                tokens.push(Token::Space);
+            }
+            Event::SoftBreak => word.push_text(" ", true),
+            Event::HardBreak => {
+                finish(&mut word, &mut tokens);
+                tokens.push(Token::HardBreak(event));
+            }
+            Event::Start(Tag::Link {
+                link_type,
+                ref dest_url,
+                ref title,
+                ref id,
+            }) => {
+                let width = syntax_width(link_type, dest_url, title, id);
+                depth += 1;
+                word.push_event(event, width);
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                ref dest_url,
+                ref title,
+                ref id,
+            }) => {
+                let width = syntax_width(link_type, dest_url, title, id) + 1;
+                depth += 1;
+                word.push_event(event, width);
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                depth = depth.saturating_sub(1);
+                word.push_event(event, 0);
+            }
+            Event::InlineHtml(ref html) => {
+                // Brackets of reference links written by the links pass
+                if html.as_ref() == "[" || html.as_ref() == "![" {
+                    depth += 1;
+                } else if depth > 0 && html.starts_with(']') {
+                    depth -= 1;
+                }
+                let width = html.chars().count();
+                word.push_event(event, width);
+            }
+            Event::Code(ref code) => {
+                let width = code.chars().count() + 2;
+                word.push_event(event, width);
+            }
+            Event::Start(Tag::Emphasis) | Event::End(TagEnd::Emphasis) => {
+                word.push_event(event, 1);
+            }
+            Event::Start(Tag::Strong | Tag::Strikethrough)
+            | Event::End(TagEnd::Strong | TagEnd::Strikethrough) => word.push_event(event, 2),
+            Event::FootnoteReference(ref label) => {
+                let width = label.chars().count() + 3;
+                word.push_event(event, width);
+            }
+            Event::TaskListMarker(_) => word.push_event(event, 3),
+            _ => word.push_event(event, 0),
+        }
+    }
+    finish(&mut word, &mut tokens);
+    tokens
+}
+
+/// Whether a line starting with `html` would be an HTML block.
+///
+/// Only the kinds of HTML block that can interrupt a paragraph count:
+/// comments, processing instructions, declarations, CDATA and the tags in
+/// [`HTML_BLOCK_TAGS`] and [`RAW_HTML_TAGS`].
+fn starts_html_block(html: &str) -> bool {
+    let html = html.to_ascii_lowercase();
+    if html.starts_with("<!") || html.starts_with("<?") {
+        return true;
+    }
+    let Some(tag) = html.strip_prefix('<') else {
+        return false;
+    };
+    let (closing, tag) = tag
+        .strip_prefix('/')
+        .map_or((false, tag), |tag| (true, tag));
+    let name_end = tag
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(tag.len());
+    let (name, rest) = tag.split_at(name_end);
+    let name_ends = rest.is_empty() || rest.starts_with(['>', '/', ' ', '\t', '\n']);
+
+    name_ends && (HTML_BLOCK_TAGS.contains(&name) || (!closing && RAW_HTML_TAGS.contains(&name)))
+}
+
+/// Columns used by the brackets and destination of a link.
+fn syntax_width(link_type: LinkType, url: &str, title: &str, id: &str) -> usize {
+    match link_type {
+        LinkType::Autolink | LinkType::Email | LinkType::Shortcut | LinkType::ShortcutUnknown => 2,
+        LinkType::Reference | LinkType::ReferenceUnknown => id.chars().count() + 4,
+        LinkType::Collapsed | LinkType::CollapsedUnknown => 4,
+        LinkType::Inline => {
+            let title = if title.is_empty() {
+                0
+            } else {
+                title.chars().count() + 3
+            };
+            url.chars().count() + 4 + title
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::parse_markdown;
+
+    fn wrap(source: &str, wrap: WrapMode, line_width: usize) -> Vec<Event<'_>> {
+        let config = ParagraphConfig { wrap, line_width };
+        wrap_paragraphs(parse_markdown(source), &config)
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect()
+    }
+
+    fn lines(events: &[Event<'_>]) -> Vec<String> {
+        let mut lines = vec![String::new()];
+        for event in events {
+            match event {
+                Event::SoftBreak => lines.push(String::new()),
+                Event::Text(text) => lines.last_mut().unwrap().push_str(text),
+                Event::Code(code) => {
+                    let line = lines.last_mut().unwrap();
+                    line.push('`');
+                    line.push_str(code);
+                    line.push('`');
+                }
+                _ => {}
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn test_wrap_always() {
+        let events = wrap(
+            "one two three four `five six` seven\neight",
+            WrapMode::Always,
+            14,
+        );
+        assert_eq!(
+            lines(&events),
+            ["one two three", "four", "`five six`", "seven eight"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_never() {
+        let events = wrap("one\ntwo [a\nlink](x)\nthree", WrapMode::Never, 80);
+        assert!(!events.contains(&Event::SoftBreak));
+        assert_eq!(lines(&events), ["one two a link three"]);
+    }
+
+    #[test]
+    fn test_wrap_keeps_structure() {
+        // Breaking before "1." or "-" would start a list
+        let events = wrap("aaaa bbbb 1. cccc - dddd", WrapMode::Always, 9);
+        assert_eq!(lines(&events), ["aaaa bbbb 1.", "cccc -", "dddd"]);
+    }
+
+    #[test]
+    fn test_wrap_keeps_block_starts() {
+        // Each of these words would start a new block at the start of a line
+        for word in [
+            "<div>x</div>",
+            "<!--c-->",
+            "<p>",
+            "</details>",
+            "<pre>",
+            "<?php?>",
+            "<![CDATA[x]]>",
+            "\\>",
+            "\\*\\*\\*",
+            "\\_\\_\\_",
+            "\\_",
+            "\\`\\`\\`x",
+            "``` `` ```",
+        ] {
+            let source = format!("aaaa bbbb {word} cccc");
+            let events = wrap(&source, WrapMode::Always, 9);
+            assert_eq!(lines(&events).len(), 2, "{word}");
+        }
+
+        let events = wrap("aaaa bbbb <span>x</span> cccc", WrapMode::Always, 9);
+        assert_eq!(lines(&events).len(), 3);
+    }
+
+    #[test]
+    fn test_wrap_sentences() {
+        let source = "One `a. B` sentence. Dr. Who, e.g. J. R. Smith, said \"hi!\" Then\n[a. B](x). Why... not? *Yes.* done";
+        let events = wrap(source, WrapMode::Sentence, 10);
+        assert_eq!(
+            lines(&events),
+            [
+                "One `a. B` sentence.",
+                "Dr. Who, e.g. J. R. Smith, said \"hi!\"",
+                "Then a. B.",
+                "Why... not?",
+                "Yes. done",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_counts_indentation() {
+        let events = wrap("> - one two three", WrapMode::Always, 11);
+        assert_eq!(lines(&events), ["one two", "three"]);
+    }
+}
@@ -54,21 +54,13 @@ pub fn format_tables(content: &str, config: &TableConfig) -> String {
 
 /// Format a single table.
 fn format_table(lines: &[String], config: &TableConfig) -> String {
-    if lines.len() < 2 {
-        // Not a valid table
+    if lines.len() < 2 || !is_delimiter_row(&split_cells(&lines[1])) {
+        // Not a valid table, such as prose with pipes in it
         return lines.join("\n") + "\n";
     }
 
     // Parse table rows
-    let rows: Vec<Vec<String>> = lines
-        .iter()
-        .map(|line| {
-            line.split('|')
-                .map(|cell| cell.trim().to_string())
-                .filter(|cell| !cell.is_empty())
-                .collect()
-        })
-        .collect();
+    let rows: Vec<Vec<String>> = lines.iter().map(|line| split_cells(line)).collect();
 
     if rows.is_empty() {
         return lines.join("\n") + "\n";
@@ -78,12 +70,9 @@ fn format_table(lines: &[String], config: &TableConfig) -> String {
     let num_cols = rows.iter().map(Vec::len).max().unwrap_or(0);
     let mut col_widths = vec![config.min_column_width; num_cols];
 
-    for row in &rows {
+    for row in rows.iter().filter(|row| !is_delimiter_row(row)) {
         for (i, cell) in row.iter().enumerate() {
-            // Skip separator rows
-            if !cell.chars().all(|c| c == '-' || c == ':') {
-                col_widths[i] = col_widths[i].max(cell.len());
-            }
+            col_widths[i] = col_widths[i].max(cell.len());
         }
     }
 
@@ -91,20 +80,20 @@ fn format_table(lines: &[String], config: &TableConfig) -> String {
     let mut formatted = String::new();
     for row in &rows {
         formatted.push('|');
+        let is_separator = is_delimiter_row(row);
 
         for (col_idx, cell) in row.iter().enumerate() {
             let width = col_widths[col_idx];
             let padding = " ".repeat(config.padding);
 
-            // Check if this is a separator row
-            if cell.chars().all(|c| c == '-' || c == ':') {
+            if is_separator {
                 // Preserve alignment indicators
                 let sep = if cell.starts_with(':') && cell.ends_with(':') {
-                    format!(":{:-<width$}:", "", width = width - 2)
+                    format!(":{:-<width$}:", "", width = width.saturating_sub(2))
                 } else if cell.ends_with(':') {
-                    format!("{:-<width$}:", "", width = width - 1)
+                    format!("{:-<width$}:", "", width = width.saturating_sub(1))
                 } else if cell.starts_with(':') {
-                    format!(":{:-<width$}", "", width = width - 1)
+                    format!(":{:-<width$}", "", width = width.saturating_sub(1))
                 } else {
                     "-".repeat(width)
                 };
@@ -125,6 +114,44 @@ fn format_table(lines: &[String], config: &TableConfig) -> String {
     formatted
 }
 
+/// Split a table row into trimmed cells.
+///
+/// Outer pipes are optional, and escaped pipes (`\|`) stay inside their cell.
+/// Empty cells are kept so that columns never shift.
+pub fn split_cells(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = if inner.ends_with('|') && !inner.ends_with("\\|") {
+        &inner[..inner.len() - 1]
+    } else {
+        inner
+    };
+
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for ch in inner.chars() {
+        if ch == '|' && !escaped {
+            cells.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(ch);
+        }
+        escaped = ch == '\\' && !escaped;
+    }
+    cells.push(current.trim().to_string());
+
+    cells
+}
+
+/// Check if a parsed row is the delimiter row (`|---|:--:|`).
+pub fn is_delimiter_row(cells: &[String]) -> bool {
+    !cells.is_empty()
+        && cells.iter().all(|cell| {
+            !cell.is_empty() && cell.contains('-') && cell.chars().all(|c| c == '-' || c == ':')
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,4 +177,20 @@ mod tests {
         let result = format_tables(input, &config);
         assert_eq!(result, input);
     }
+
+    #[test]
+    fn test_split_cells() {
+        assert_eq!(split_cells("| a | b |"), vec!["a", "b"]);
+        assert_eq!(split_cells("|a||c|"), vec!["a", "", "c"]);
+        assert_eq!(split_cells("| x \\| y | z |"), vec!["x \\| y", "z"]);
+    }
+
+    #[test]
+    fn test_format_table_keeps_empty_and_escaped_cells() {
+        let input = "|a|b|c|\n|---|---|---|\n|1||3|\n|x \\| y|z|w|";
+        let result = format_tables(input, &TableConfig::default());
+
+        assert!(result.contains("| 1      |     | 3   |"));
+        assert!(result.contains("| x \\| y | z   | w   |"));
+    }
 }
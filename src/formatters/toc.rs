@@ -0,0 +1,273 @@
+//! Table of contents module.
+//!
+//! Generates a nested list of heading links between `<!-- toc -->` and
+//! `<!-- tocstop -->` marker comments.
+
+use std::ops::Range;
+
+use pulldown_cmark::{CowStr, Event, LinkType, Tag, TagEnd};
+
+use super::line_number;
+use crate::config::{SlugStyle, TocConfig};
+use crate::diagnostics::{DiagnosticKind, Diagnostics};
+use crate::slug::Slugger;
+
+/// Marker comment that starts the table of contents.
+const TOC_START: &str = "<!--toc-->";
+
+/// Marker comment that ends the table of contents.
+const TOC_STOP: &str = "<!--tocstop-->";
+
+/// A heading listed in the table of contents.
+struct TocEntry {
+    level: usize,
+    /// Link text: the heading's text and code spans.
+    label: Vec<Event<'static>>,
+    slug: String,
+}
+
+/// Rewrite the table of contents between the marker comments.
+///
+/// Everything between the markers is replaced by a freshly generated list,
+/// so the TOC stays in sync with the headings. Documents without markers
+/// are returned unchanged. Links use `slug_style` anchors.
+pub fn insert_toc<'a>(
+    events: Vec<(Event<'a>, Range<usize>)>,
+    source: &str,
+    config: &TocConfig,
+    slug_style: SlugStyle,
+    diagnostics: &mut Diagnostics,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let Some(start) = events
+        .iter()
+        .position(|(event, _)| is_marker(event, TOC_START))
+    else {
+        return events;
+    };
+    let Some(stop) = events[start..]
+        .iter()
+        .position(|(event, _)| is_marker(event, TOC_STOP))
+        .map(|pos| start + pos)
+    else {
+        diagnostics.warn(
+            DiagnosticKind::Other,
+            line_number(source, events[start].1.start),
+            "Found <!-- toc --> without a matching <!-- tocstop -->; TOC not updated",
+        );
+        return events;
+    };
+
+    // Replace everything between the two HTML blocks holding the markers
+    let region_start = events[start..stop]
+        .iter()
+        .position(|(event, _)| matches!(event, Event::End(TagEnd::HtmlBlock)))
+        .map_or(start + 1, |pos| start + pos + 1);
+    let region_end = events[region_start..stop]
+        .iter()
+        .rposition(|(event, _)| matches!(event, Event::Start(Tag::HtmlBlock)))
+        .map_or(stop, |pos| region_start + pos);
+
+    // The heading of the section the TOC lives in
+    let own_heading = events[..start]
+        .iter()
+        .rposition(|(event, _)| matches!(event, Event::Start(Tag::Heading { .. })));
+
+    let entries: Vec<TocEntry> = collect_headings(&events, slug_style)
+        .into_iter()
+        .filter(|(index, _)| !(region_start..region_end).contains(index))
+        .filter(|(index, _)| !(config.exclude_heading && Some(*index) == own_heading))
+        .map(|(_, entry)| entry)
+        .filter(|entry| (config.min_depth..=config.max_depth).contains(&entry.level))
+        .collect();
+
+    let range = events[start].1.clone();
+    let mut toc = Vec::new();
+    if !entries.is_empty() {
+        push_toc_list(&entries, &mut toc);
+    }
+
+    let mut events = events;
+    events.splice(
+        region_start..region_end,
+        toc.into_iter().map(|event| (event, range.clone())),
+    );
+    events
+}
+
+/// Check if an event is the given TOC marker comment.
+fn is_marker(event: &Event<'_>, marker: &str) -> bool {
+    match event {
+        Event::Html(html) => html.split_whitespace().collect::<String>() == marker,
+        _ => false,
+    }
+}
+
+/// Collect every heading with its event index and unique slug.
+///
+/// All headings take part in slug disambiguation, including the ones that
+/// end up excluded from the TOC, so the anchors match the rendered page.
+fn collect_headings(
+    events: &[(Event<'_>, Range<usize>)],
+    slug_style: SlugStyle,
+) -> Vec<(usize, TocEntry)> {
+    let mut slugger = Slugger::with_style(slug_style);
+    let mut headings = Vec::new();
+    let mut current: Option<(usize, usize, Option<String>, Vec<Event<'static>>)> = None;
+
+    for (index, (event, _)) in events.iter().enumerate() {
+        match event {
+            Event::Start(Tag::Heading { level, id, .. }) => {
+                let id = id.as_ref().map(ToString::to_string);
+                current = Some((index, *level as usize, id, Vec::new()));
+            }
+            Event::Text(_) | Event::Code(_) => {
+                if let Some((_, _, _, label)) = current.as_mut() {
+                    label.push(event.clone().into_static());
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((start, level, id, label)) = current.take() {
+                    let slug = match id {
+                        Some(id) => slugger.unique(&id),
+                        None => slugger.slug(&plain_text(&label)),
+                    };
+                    headings.push((
+                        start,
+                        TocEntry {
+                            level,
+                            label: trim_label(label),
+                            slug,
+                        },
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    headings
+}
+
+/// The text of a heading label, with code spans as plain text.
+fn plain_text(label: &[Event<'_>]) -> String {
+    label
+        .iter()
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Trim the whitespace around the text of a heading label.
+fn trim_label(mut label: Vec<Event<'static>>) -> Vec<Event<'static>> {
+    if let Some(Event::Text(text)) = label.first_mut() {
+        *text = CowStr::from(text.trim_start().to_string());
+    }
+    if let Some(Event::Text(text)) = label.last_mut() {
+        *text = CowStr::from(text.trim_end().to_string());
+    }
+    label.retain(|event| !matches!(event, Event::Text(text) if text.is_empty()));
+    label
+}
+
+/// Push a nested list of links for `entries`.
+///
+/// Each entry's children are the following entries with a deeper level.
+fn push_toc_list(entries: &[TocEntry], events: &mut Vec<Event<'_>>) {
+    events.push(Event::Start(Tag::List(None)));
+
+    let mut i = 0;
+    while i < entries.len() {
+        let entry = &entries[i];
+        let end = entries[i + 1..]
+            .iter()
+            .position(|next| next.level <= entry.level)
+            .map_or(entries.len(), |pos| i + 1 + pos);
+
+        events.push(Event::Start(Tag::Item));
+        events.push(Event::Start(Tag::Link {
+            link_type: LinkType::Inline,
+            dest_url: CowStr::from(format!("#{}", entry.slug)),
+            title: CowStr::Borrowed(""),
+            id: CowStr::Borrowed(""),
+        }));
+        events.extend(entry.label.iter().cloned());
+        events.push(Event::End(TagEnd::Link));
+        if end > i + 1 {
+            push_toc_list(&entries[i + 1..end], events);
+        }
+        events.push(Event::End(TagEnd::Item));
+
+        i = end;
+    }
+
+    events.push(Event::End(TagEnd::List(false)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toc(source: &str, config: &TocConfig) -> String {
+        let events = pulldown_cmark::Parser::new_ext(
+            source,
+            pulldown_cmark::Options::ENABLE_HEADING_ATTRIBUTES,
+        )
+        .into_offset_iter()
+        .collect();
+        let events = insert_toc(
+            events,
+            source,
+            config,
+            SlugStyle::Github,
+            &mut Diagnostics::new(),
+        );
+
+        let mut buf = String::new();
+        pulldown_cmark_to_cmark::cmark(events.iter().map(|(event, _)| event), &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_insert_toc() {
+        let source = "# Doc\n\n## Contents\n\n<!-- toc -->\n- [Old](#old)\n<!-- tocstop -->\n\n## Install {#setup}\n\n### From `cargo`\n\n## Usage\n\n## Usage\n";
+        let result = toc(source, &TocConfig::default());
+
+        assert!(result.contains(
+            "<!-- toc -->\n\n* [Doc](#doc)\n  * [Install](#setup)\n    * [From `cargo`](#from-cargo)\n  * [Usage](#usage)\n  * [Usage](#usage-1)\n\n<!-- tocstop -->"
+        ));
+        assert!(!result.contains("#old"));
+    }
+
+    #[test]
+    fn test_insert_toc_depth() {
+        let source = "# Doc\n\n<!-- toc -->\n<!-- tocstop -->\n\n## A\n\n### B\n";
+        let config = TocConfig {
+            min_depth: 2,
+            max_depth: 2,
+            ..Default::default()
+        };
+
+        assert!(toc(source, &config).contains("<!-- toc -->\n\n* [A](#a)\n\n<!-- tocstop -->"));
+    }
+
+    #[test]
+    fn test_insert_toc_without_stop_marker() {
+        let source = "# Doc\n\n<!-- toc -->\n\n## A\n";
+        let events: Vec<_> = pulldown_cmark::Parser::new(source)
+            .into_offset_iter()
+            .collect();
+        let mut diagnostics = Diagnostics::new();
+
+        let result = insert_toc(
+            events.clone(),
+            source,
+            &TocConfig::default(),
+            SlugStyle::Github,
+            &mut diagnostics,
+        );
+        assert_eq!(result, events);
+        assert_eq!(diagnostics.len(), 1);
+    }
+}
@@ -58,7 +58,10 @@ pub mod diagnostics;
 pub mod error;
 mod formatter;
 mod formatters;
+pub mod linkcheck;
 mod preprocessor;
+mod slug;
+pub mod stats;
 
 // Re-export main types for convenience
 pub use config::Config;
@@ -89,14 +92,22 @@ pub fn format_markdown(content: &str, config: &Config) -> Result<(String, Diagno
 
 /// Format a markdown file in-place.
 ///
-/// Returns diagnostics collected during processing.
+/// Returns diagnostics collected during processing, including broken local
+/// links when `links.check_local` is enabled.
 ///
 /// # Errors
 ///
 /// Returns an error if the file cannot be read, parsed, formatted, or written.
 pub fn format_file<P: AsRef<std::path::Path>>(path: P, config: &Config) -> Result<Diagnostics> {
     let content = std::fs::read_to_string(path.as_ref())?;
-    let (formatted, diagnostics) = format_markdown(&content, config)?;
+    let (formatted, mut diagnostics) = format_markdown(&content, config)?;
+    if config.links.check_local {
+        diagnostics.extend(linkcheck::check_links(
+            &content,
+            path.as_ref(),
+            config.headings.slug_style,
+        ));
+    }
     std::fs::write(path.as_ref(), formatted)?;
     Ok(diagnostics)
 }
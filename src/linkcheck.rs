@@ -0,0 +1,241 @@
+//! Local link validation.
+//!
+//! Resolves relative file links, images and `#anchor` fragments against the
+//! files on disk and the headings of the linked Markdown files. Nothing is
+//! fetched over the network: links with a scheme are skipped.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use pulldown_cmark::{Event, LinkType, Tag};
+
+use crate::config::SlugStyle;
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, Severity};
+use crate::formatter::{parse_markdown, split_front_matter};
+use crate::formatters::img_sources;
+use crate::slug::document_anchors;
+
+/// Check the local links of the Markdown `content` read from `path`.
+///
+/// Relative links, images and `<img>` tags are resolved against the
+/// directory of `path`. Fragments are checked against the heading anchors of
+/// the target, generated with `slug_style`, when the target is a Markdown
+/// file. Each broken link is reported as a warning with its line and column.
+///
+/// # Examples
+///
+/// ```
+/// use beautiful_md::config::SlugStyle;
+/// use beautiful_md::linkcheck::check_links;
+///
+/// let content = "# Setup\n\n[Setup](#setup) and [missing](#install)";
+/// let diagnostics = check_links(content, "README.md".as_ref(), SlugStyle::Github);
+/// assert_eq!(diagnostics.len(), 1);
+/// ```
+#[must_use]
+pub fn check_links(content: &str, path: &Path, slug_style: SlugStyle) -> Diagnostics {
+    let (_, body) = split_front_matter(content);
+    let offset = content.len() - body.len();
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut own_anchors = None;
+    let mut diagnostics = Diagnostics::new();
+
+    for (event, range) in parse_markdown(body) {
+        let targets = match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) if !is_autolink(link_type) => vec![(range.start, dest_url, false)],
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) if !is_autolink(link_type) => vec![(range.start, dest_url, true)],
+            Event::Html(html) | Event::InlineHtml(html) => img_sources(&html)
+                .into_iter()
+                .map(|(pos, src)| (range.start + pos, src.into(), true))
+                .collect(),
+            _ => continue,
+        };
+        for (start, dest_url, image) in targets {
+            if let Some(message) = check_target(
+                &dest_url,
+                image,
+                directory,
+                content,
+                slug_style,
+                &mut own_anchors,
+            ) {
+                let (line, column) = position(content, offset + start);
+                diagnostics.add(
+                    Diagnostic::new(Severity::Warning, DiagnosticKind::Link, line, message)
+                        .with_column(column),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Check one link or image destination, returning the problem with it.
+fn check_target(
+    dest_url: &str,
+    image: bool,
+    directory: &Path,
+    content: &str,
+    slug_style: SlugStyle,
+    own_anchors: &mut Option<HashSet<String>>,
+) -> Option<String> {
+    if has_scheme(dest_url) {
+        return None;
+    }
+
+    let (target, fragment) = dest_url
+        .split_once('#')
+        .map_or((dest_url, None), |(target, fragment)| {
+            (target, Some(percent_decode(fragment)))
+        });
+    let target = percent_decode(target.split('?').next().unwrap_or_default());
+
+    if target.is_empty() {
+        let anchors = own_anchors.get_or_insert_with(|| document_anchors(content, slug_style));
+        fragment
+            .filter(|fragment| !is_anchor(anchors, fragment))
+            .map(|fragment| format!("Broken link: no heading for #{fragment}"))
+    } else if target.starts_with('/') {
+        // Site-absolute links depend on where the docs are served from
+        None
+    } else {
+        let file = directory.join(&target);
+        if !file.exists() {
+            let what = if image {
+                "Missing image"
+            } else {
+                "Broken link"
+            };
+            Some(format!("{what}: {target} does not exist"))
+        } else if let (Some(fragment), true) = (fragment, is_markdown(&file)) {
+            let anchors = std::fs::read_to_string(&file)
+                .map(|linked| document_anchors(&linked, slug_style))
+                .unwrap_or_default();
+            (!is_anchor(&anchors, &fragment))
+                .then(|| format!("Broken link: no heading for #{fragment} in {target}"))
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether links of this type point at their text rather than a file.
+const fn is_autolink(link_type: LinkType) -> bool {
+    matches!(link_type, LinkType::Autolink | LinkType::Email)
+}
+
+/// Whether a link destination starts with a URL scheme (`https:`, `mailto:`)
+/// or is protocol-relative (`//host`).
+fn has_scheme(url: &str) -> bool {
+    if url.starts_with("//") {
+        return true;
+    }
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Whether `fragment` names one of the `anchors`, ignoring case.
+fn is_anchor(anchors: &HashSet<String>, fragment: &str) -> bool {
+    fragment.is_empty() || anchors.contains(fragment) || anchors.contains(&fragment.to_lowercase())
+}
+
+/// Whether `path` is a Markdown file.
+fn is_markdown(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                matches!(
+                    extension.to_ascii_lowercase().as_str(),
+                    "md" | "markdown" | "mdown" | "mkd"
+                )
+            })
+}
+
+/// Decode `%XX` escapes in a link destination.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escape {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// 1-indexed line and column of a byte offset.
+fn position(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_links() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path();
+        std::fs::write(directory.join("guide.md"), "# Getting Started\n").unwrap();
+
+        let content = "---\ntitle: x\n---\n# Intro\n\n\
+                       [a](guide.md#getting-started) [b](guide.md#nope) [c](missing.md)\n\
+                       [d](#intro) [e](#Intro) [f](#gone) [g](https://example.com/x.md) [h](guide%2Emd)\n\
+                       ![i](guide.md) ![j](gone.png) <img src=\"guide.md\"> <img src=\"lost.png\">\n\n\
+                       <p><img src=\"block.png\"></p>";
+        let diagnostics = check_links(content, &directory.join("README.md"), SlugStyle::Github);
+        let found: Vec<_> = diagnostics
+            .messages()
+            .iter()
+            .map(|d| (d.line, d.column, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (6, Some(31), "Broken link: no heading for #nope in guide.md"),
+                (6, Some(50), "Broken link: missing.md does not exist"),
+                (7, Some(25), "Broken link: no heading for #gone"),
+                (8, Some(16), "Missing image: gone.png does not exist"),
+                (8, Some(52), "Missing image: lost.png does not exist"),
+                (10, Some(4), "Missing image: block.png does not exist"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_has_scheme() {
+        assert!(has_scheme("mailto:me@example.com"));
+        assert!(has_scheme("//cdn.example.com/x.js"));
+        assert!(!has_scheme("docs/setup.md"));
+        assert!(!has_scheme("#a:b"));
+    }
+}
@@ -3,7 +3,9 @@
 #![allow(clippy::multiple_crate_versions)]
 
 use anyhow::{Context, Result};
-use beautiful_md::{format_file, format_markdown, Config};
+use beautiful_md::linkcheck::check_links;
+use beautiful_md::stats::task_stats;
+use beautiful_md::{format_file, format_markdown, Config, Diagnostics};
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
@@ -96,6 +98,15 @@ fn handle_subcommand(command: Commands, config: &Config) -> Result<()> {
         Commands::Check { files } => {
             return check_files(&files, config);
         }
+        Commands::CheckLinks { files } => {
+            return check_links_in_files(&files, config);
+        }
+        Commands::Stats { files, tasks } => {
+            if !tasks {
+                anyhow::bail!("No statistics selected. Use --tasks to report task list progress.");
+            }
+            return print_task_stats(&files);
+        }
     }
     Ok(())
 }
@@ -131,8 +142,9 @@ fn format_to_file(input: &Path, output: &Path, config: &Config) -> Result<()> {
     let content =
         fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
 
-    let (formatted, diagnostics) = format_markdown(&content, config)
+    let (formatted, mut diagnostics) = format_markdown(&content, config)
         .with_context(|| format!("Failed to format {}", input.display()))?;
+    lint_links(&content, input, config, &mut diagnostics);
 
     fs::write(output, formatted)
         .with_context(|| format!("Failed to write to {}", output.display()))?;
@@ -155,8 +167,9 @@ fn format_to_stdout(files: &[std::path::PathBuf], config: &Config) -> Result<()>
         let content = fs::read_to_string(file)
             .with_context(|| format!("Failed to read {}", file.display()))?;
 
-        let (formatted, diagnostics) = format_markdown(&content, config)
+        let (formatted, mut diagnostics) = format_markdown(&content, config)
             .with_context(|| format!("Failed to format {}", file.display()))?;
+        lint_links(&content, file, config, &mut diagnostics);
 
         writeln!(handle, "{formatted}").context("Failed to write to stdout")?;
 
@@ -207,6 +220,65 @@ fn check_files(files: &[std::path::PathBuf], config: &Config) -> Result<()> {
     }
 }
 
+/// Add broken local link diagnostics when `links.check_local` is enabled.
+fn lint_links(content: &str, file: &Path, config: &Config, diagnostics: &mut Diagnostics) {
+    if config.links.check_local {
+        diagnostics.extend(check_links(content, file, config.headings.slug_style));
+    }
+}
+
+/// Report broken relative links and anchors, failing if any are found.
+fn check_links_in_files(files: &[std::path::PathBuf], config: &Config) -> Result<()> {
+    let mut broken = 0;
+
+    for file in files {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let diagnostics = check_links(&content, file, config.headings.slug_style);
+
+        if !diagnostics.is_empty() {
+            broken += diagnostics.len();
+            eprintln!("\n{}:", colors::path(file.display().to_string()));
+            diagnostics.print_to_stderr_colored();
+        }
+    }
+
+    if broken == 0 {
+        println!("{}", colors::success("✓ No broken links found"));
+        Ok(())
+    } else {
+        anyhow::bail!("{broken} broken link(s) found");
+    }
+}
+
+/// Print done/total task list items per file.
+fn print_task_stats(files: &[std::path::PathBuf]) -> Result<()> {
+    let (mut done, mut total) = (0, 0);
+
+    for file in files {
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let stats = task_stats(&content);
+        done += stats.done;
+        total += stats.total;
+
+        println!(
+            "{}: {}/{} tasks done",
+            colors::path(file.display().to_string()),
+            stats.done,
+            stats.total
+        );
+    }
+
+    if files.len() > 1 {
+        println!(
+            "{}",
+            colors::bold(format!("Total: {done}/{total} tasks done"))
+        );
+    }
+    Ok(())
+}
+
 /// Dry run: analyze files and report issues without modifying them.
 fn dry_run_files(files: &[std::path::PathBuf], config: &Config) -> Result<()> {
     let mut total_issues = 0;
@@ -215,8 +287,9 @@ fn dry_run_files(files: &[std::path::PathBuf], config: &Config) -> Result<()> {
         let content = fs::read_to_string(file)
             .with_context(|| format!("Failed to read {}", file.display()))?;
 
-        let (_formatted, diagnostics) = format_markdown(&content, config)
+        let (_formatted, mut diagnostics) = format_markdown(&content, config)
             .with_context(|| format!("Failed to analyze {}", file.display()))?;
+        lint_links(&content, file, config, &mut diagnostics);
 
         println!("\n📄 {}", colors::path(file.display().to_string()));
 
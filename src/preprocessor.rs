@@ -3,22 +3,56 @@
 //! This module intelligently fixes malformed markdown so it can be properly
 //! parsed and formatted, rather than being escaped or ignored.
 
+use crate::config::{ClosingHashes, Config, HeadingConfig, RaggedRows, TableConfig};
 use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, Severity};
+use crate::formatters::{is_delimiter_row, split_cells};
 
 /// Maximum heading level supported by Markdown specification (h1 through h6).
 const MAX_HEADING_LEVEL: usize = 6;
 
+/// HTML elements that cannot be represented inside a Markdown table cell.
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "ul",
+];
+
 /// Pre-process markdown content to fix common issues.
 ///
 /// Returns the preprocessed content and any diagnostics collected.
-pub fn preprocess(content: &str) -> (String, Diagnostics) {
+pub fn preprocess(content: &str, config: &Config) -> (String, Diagnostics) {
     let mut diagnostics = Diagnostics::new();
     let mut result = content.to_string();
 
     // Apply pre-processors in order
-    result = fix_headings(&result);
+    if config.tables.convert_html {
+        result = convert_html_tables(&result, &mut diagnostics);
+    }
+    result = fix_headings(&result, &config.headings);
     result = fix_list_markers(&result);
-    result = fix_table_pipes(&result, &mut diagnostics);
+    result = fix_table_pipes(&result, &config.tables, &mut diagnostics);
 
     (result, diagnostics)
 }
@@ -29,7 +63,10 @@ pub fn preprocess(content: &str) -> (String, Diagnostics) {
 /// - `#NoSpace` → `# NoSpace`
 /// - `####Trailing####` → `#### Trailing`
 /// - `###  TooManySpaces` → `### TooManySpaces`
-fn fix_headings(content: &str) -> String {
+///
+/// Closing hashes are normalized to match the opening ones instead of being
+/// removed unless `closing_hashes` is `"remove"`.
+fn fix_headings(content: &str, config: &HeadingConfig) -> String {
     let lines: Vec<String> = content
         .lines()
         .map(|line| {
@@ -60,21 +97,25 @@ fn fix_headings(content: &str) -> String {
                             return line.to_string();
                         }
 
-                        // Remove trailing hashes
-                        let mut content = rest_trimmed.to_string();
-                        while content.ends_with('#') {
-                            content.pop();
-                        }
-                        let content = content.trim_end();
+                        // Remove trailing hashes. A closing sequence must be preceded
+                        // by a space (`# C#` keeps its hash), unless the heading had no
+                        // space after the opening hashes and is malformed anyway.
+                        let without_hashes = rest_trimmed.trim_end_matches('#');
+                        let has_closing = without_hashes.len() < rest_trimmed.len()
+                            && (!ch.is_whitespace()
+                                || without_hashes.ends_with(char::is_whitespace));
+                        let text = if has_closing {
+                            without_hashes.trim()
+                        } else {
+                            rest_trimmed
+                        };
 
                         // Reconstruct heading with proper spacing
-                        return if ch.is_whitespace() {
-                            // Already has space after hashes, just normalize
-                            let text = rest_trimmed.trim_end_matches('#').trim();
-                            format!("{hashes} {text}")
+                        let keep_closing = config.closing_hashes != ClosingHashes::Remove;
+                        return if has_closing && keep_closing {
+                            format!("{hashes} {text} {hashes}")
                         } else {
-                            // No space after hashes, add it
-                            format!("{hashes} {content}")
+                            format!("{hashes} {text}")
                         };
                     }
                 }
@@ -114,6 +155,11 @@ fn fix_list_markers(content: &str) -> String {
             let leading_spaces = line.len() - line.trim_start().len();
             let trimmed = line.trim_start();
 
+            // Leave setext underlines (`Title\n--`) alone
+            if !trimmed.is_empty() && trimmed.chars().all(|c| c == '-') {
+                return line.to_string();
+            }
+
             // Check for unordered list without space
             if trimmed.starts_with('-') && !trimmed.starts_with("---") && !trimmed.starts_with("- ")
             {
@@ -121,8 +167,12 @@ fn fix_list_markers(content: &str) -> String {
                 return format_list_line(leading_spaces, "-", rest);
             }
 
-            // Skip bold text (** at start) - not a list marker
-            if trimmed.starts_with("**") {
+            // Skip bold text (** at start) and `*emphasis*` - not a list marker
+            if trimmed.starts_with("**")
+                || trimmed
+                    .strip_prefix('*')
+                    .is_some_and(|rest| rest.contains('*'))
+            {
                 return line.to_string();
             }
 
@@ -161,14 +211,16 @@ fn fix_list_markers(content: &str) -> String {
 /// Fixes:
 /// - Missing opening pipes: `Name|Age` → `|Name|Age|`
 /// - Missing closing pipes: `Name|Age` → `|Name|Age|`
-fn fix_table_pipes(content: &str, diagnostics: &mut Diagnostics) -> String {
+///
+/// Consecutive pipe rows are then repaired as a table block (see [`repair_table`]).
+/// Lines whose pipes are all inside code spans are not table rows.
+fn fix_table_pipes(content: &str, config: &TableConfig, diagnostics: &mut Diagnostics) -> String {
     let mut lines: Vec<String> = Vec::new();
     // Note: Code blocks are already extracted by formatter, but we keep
     // this tracking for defensive programming in case architecture changes.
     let mut in_code_block = false;
     let mut line_number = 0;
-    let mut in_table = false;
-    let mut expected_columns: Option<usize> = None;
+    let mut table: Vec<(usize, String)> = Vec::new();
 
     for line in content.lines() {
         line_number += 1;
@@ -176,6 +228,8 @@ fn fix_table_pipes(content: &str, diagnostics: &mut Diagnostics) -> String {
 
         // Track code blocks (defensive check - blocks already extracted upstream)
         if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            lines.extend(repair_table(&table, config, diagnostics));
+            table.clear();
             in_code_block = !in_code_block;
             lines.push(line.to_string());
             continue;
@@ -188,86 +242,522 @@ fn fix_table_pipes(content: &str, diagnostics: &mut Diagnostics) -> String {
         }
 
         // Check if line contains pipes (potential table row)
-        if trimmed.contains('|') && !trimmed.starts_with('>') {
-            let mut fixed = trimmed.to_string();
-            let mut had_issues = false;
-
-            // Mark start of table
-            if !in_table {
-                in_table = true;
-                expected_columns = None;
-            }
+        if has_table_pipe(trimmed) && !trimmed.starts_with('>') {
+            table.push((line_number, line.to_string()));
+        } else {
+            // Not a table line
+            lines.extend(repair_table(&table, config, diagnostics));
+            table.clear();
+            lines.push(line.to_string());
+        }
+    }
+    lines.extend(repair_table(&table, config, diagnostics));
 
-            // Add opening pipe if missing
-            if !fixed.starts_with('|') {
-                fixed = format!("|{fixed}");
-                had_issues = true;
-            }
+    lines.join("\n")
+}
 
-            // Add closing pipe if missing
-            if !fixed.ends_with('|') {
-                fixed.push('|');
-                had_issues = true;
+/// Whether a line has a `|` outside code spans and escapes.
+fn has_table_pipe(line: &str) -> bool {
+    let mut chars = line.chars().peekable();
+    let mut code_fence = 0;
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if code_fence == 0 => {
+                chars.next();
             }
-
-            // Count columns
-            let columns = fixed.split('|').filter(|s| !s.trim().is_empty()).count();
-
-            // Check if this is a separator row
-            let is_separator = fixed
-                .split('|')
-                .filter(|s| !s.trim().is_empty())
-                .all(|cell| {
-                    let trimmed = cell.trim();
-                    trimmed.chars().all(|c| c == '-' || c == ':')
-                });
-
-            if !is_separator {
-                // Set expected columns from first data row
-                if expected_columns.is_none() {
-                    expected_columns = Some(columns);
-                } else if let Some(expected) = expected_columns {
-                    // Check for column mismatch
-                    if columns != expected {
-                        diagnostics.add(
-                            Diagnostic::new(
-                                Severity::Warning,
-                                DiagnosticKind::MalformedTable,
-                                line_number,
-                                format!(
-                                    "Table has inconsistent columns: expected {expected}, found {columns}"
-                                ),
-                            )
-                            .with_snippet(trimmed),
-                        );
-                    }
+            '`' => {
+                let mut run = 1;
+                while chars.next_if_eq(&'`').is_some() {
+                    run += 1;
+                }
+                if code_fence == 0 {
+                    code_fence = run;
+                } else if code_fence == run {
+                    code_fence = 0;
                 }
             }
+            '|' if code_fence == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Add missing opening and closing pipes to a table row.
+fn fix_row_pipes(line_number: usize, row: &str, diagnostics: &mut Diagnostics) -> String {
+    let trimmed = row.trim();
+    let mut fixed = trimmed.to_string();
+
+    // Add opening pipe if missing
+    if !fixed.starts_with('|') {
+        fixed = format!("|{fixed}");
+    }
+
+    // Add closing pipe if missing
+    if !fixed.ends_with('|') {
+        fixed.push('|');
+    }
+
+    if fixed != trimmed {
+        diagnostics.add(
+            Diagnostic::new(
+                Severity::Info,
+                DiagnosticKind::MalformedTable,
+                line_number,
+                "Fixed missing table pipes",
+            )
+            .with_snippet(format!("{trimmed} → {fixed}")),
+        );
+    }
+    fixed
+}
+
+/// Repair the structure of a block of pipe rows.
+///
+/// A block of several rows without a delimiter row is only treated as a
+/// table when every row starts with a pipe; otherwise it is more likely
+/// prose and is returned unchanged.
+///
+/// Fixes:
+/// - Missing opening and closing pipes
+/// - Missing delimiter row after the header
+/// - Delimiter row with the wrong number of columns
+/// - Ragged rows, padded or merged according to [`RaggedRows`]
+///
+/// Rows that need no repair are returned unchanged.
+fn repair_table(
+    rows: &[(usize, String)],
+    config: &TableConfig,
+    diagnostics: &mut Diagnostics,
+) -> Vec<String> {
+    let prose = rows.len() >= 2
+        && !is_delimiter_row(&split_cells(&rows[1].1))
+        && !rows
+            .iter()
+            .all(|(_, row)| row.trim_start().starts_with('|'));
+    if prose {
+        return rows.iter().map(|(_, row)| row.clone()).collect();
+    }
+
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|(line_number, row)| fix_row_pipes(*line_number, row, diagnostics))
+        .collect();
+    let mut cells: Vec<Vec<String>> = lines.iter().map(|row| split_cells(row)).collect();
+    let mut line_numbers: Vec<usize> = rows.iter().map(|(line_number, _)| *line_number).collect();
+
+    // A single pipe row is more likely prose than a table
+    if cells.len() < 2 || is_delimiter_row(&cells[0]) {
+        return lines;
+    }
 
-            if had_issues {
+    if !is_delimiter_row(&cells[1]) {
+        let delimiter = vec![String::from("---"); cells[0].len()];
+        let row = render_table_row(&delimiter);
+        diagnostics.add(
+            Diagnostic::new(
+                Severity::Info,
+                DiagnosticKind::MalformedTable,
+                line_numbers[0],
+                "Inserted missing table delimiter row",
+            )
+            .with_snippet(format!("(missing) → {row}")),
+        );
+        lines.insert(1, row);
+        cells.insert(1, delimiter);
+        line_numbers.insert(1, line_numbers[0]);
+    }
+
+    let header_columns = cells[0].len();
+    let columns = match config.ragged_rows {
+        RaggedRows::Pad => cells
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != 1)
+            .map(|(_, row)| row.len())
+            .max()
+            .unwrap_or(header_columns),
+        RaggedRows::Merge | RaggedRows::Preserve => header_columns,
+    };
+
+    for (index, row) in cells.iter_mut().enumerate() {
+        let found = row.len();
+        if found == columns {
+            continue;
+        }
+        let before = lines[index].clone();
+
+        let message = if index == 1 {
+            // The parser only accepts a delimiter row matching the header
+            row.resize(columns, String::from("---"));
+            format!("Rebuilt table delimiter row for {columns} columns")
+        } else if config.ragged_rows == RaggedRows::Preserve {
+            diagnostics.add(
+                Diagnostic::new(
+                    Severity::Warning,
+                    DiagnosticKind::MalformedTable,
+                    line_numbers[index],
+                    format!("Table has inconsistent columns: expected {columns}, found {found}"),
+                )
+                .with_snippet(before),
+            );
+            continue;
+        } else if found < columns {
+            row.resize(columns, String::new());
+            format!("Padded table row from {found} to {columns} columns")
+        } else {
+            // Extra cells usually come from an unescaped pipe inside the last cell
+            let merged = row.split_off(columns - 1).join(" \\| ");
+            row.push(merged);
+            format!(
+                "Merged {} extra cell(s) into the last column",
+                found - columns
+            )
+        };
+
+        lines[index] = render_table_row(row);
+        diagnostics.add(
+            Diagnostic::new(
+                Severity::Info,
+                DiagnosticKind::MalformedTable,
+                line_numbers[index],
+                message,
+            )
+            .with_snippet(format!("{before} → {}", lines[index])),
+        );
+    }
+
+    lines
+}
+
+/// Render table cells as a pipe row.
+fn render_table_row(cells: &[String]) -> String {
+    format!("|{}|", cells.join("|"))
+}
+
+/// Convert simple HTML tables into Markdown pipe tables.
+///
+/// Only tables with a single header row and no `colspan`, `rowspan` or
+/// block-level cell content are converted. Anything else is left untouched
+/// and reported with the reason it was skipped.
+fn convert_html_tables(content: &str, diagnostics: &mut Diagnostics) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut result: Vec<String> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if !line.trim_start().to_ascii_lowercase().starts_with("<table") {
+            result.push(line.to_string());
+            i += 1;
+            continue;
+        }
+
+        // Find the closing tag; an unclosed table is not ours to touch
+        let Some(end) =
+            (i..lines.len()).find(|&j| lines[j].to_ascii_lowercase().contains("</table>"))
+        else {
+            result.push(line.to_string());
+            i += 1;
+            continue;
+        };
+
+        let block = lines[i..=end].join("\n");
+        match html_table_to_rows(&block) {
+            Ok(rows) => {
+                let indent = &line[..line.len() - line.trim_start().len()];
+
+                // Pipe tables need to stand on their own, unlike HTML blocks
+                if result.last().is_some_and(|prev| !prev.trim().is_empty()) {
+                    result.push(String::new());
+                }
+                result.extend(rows.iter().map(|row| format!("{indent}{row}")));
+                if lines
+                    .get(end + 1)
+                    .is_some_and(|next| !next.trim().is_empty())
+                {
+                    result.push(String::new());
+                }
+
+                diagnostics.info(
+                    DiagnosticKind::HtmlTable,
+                    i + 1,
+                    "Converted HTML table to Markdown",
+                );
+            }
+            Err(reason) => {
                 diagnostics.add(
                     Diagnostic::new(
                         Severity::Info,
-                        DiagnosticKind::MalformedTable,
-                        line_number,
-                        "Fixed missing table pipes",
+                        DiagnosticKind::HtmlTable,
+                        i + 1,
+                        format!("HTML table not converted: {reason}"),
                     )
-                    .with_snippet(format!("{trimmed} → {fixed}")),
+                    .with_snippet(line.trim()),
                 );
+                result.extend(lines[i..=end].iter().map(ToString::to_string));
             }
+        }
+
+        i = end + 1;
+    }
+
+    result.join("\n")
+}
+
+/// A cell collected from an HTML table.
+struct HtmlCell {
+    is_header: bool,
+    delimiter: &'static str,
+    content: String,
+}
+
+/// A row collected from an HTML table.
+struct HtmlRow {
+    in_thead: bool,
+    cells: Vec<HtmlCell>,
+}
+
+/// Parse an HTML table block into Markdown table rows.
+///
+/// Returns the reason the table cannot be converted on failure.
+fn html_table_to_rows(block: &str) -> std::result::Result<Vec<String>, String> {
+    let rows = parse_html_rows(block)?;
+
+    let Some((header, body)) = rows.split_first() else {
+        return Err(String::from("has no rows"));
+    };
+    if !header.in_thead && !header.cells.iter().all(|cell| cell.is_header) {
+        return Err(String::from("has no header row"));
+    }
+    if body.iter().any(|row| row.in_thead) {
+        return Err(String::from("has more than one header row"));
+    }
+
+    let columns = rows.iter().map(|row| row.cells.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return Err(String::from("has no cells"));
+    }
+
+    let render = |cells: Vec<String>| {
+        let mut cells = cells;
+        cells.resize(columns, String::new());
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![render(
+        header
+            .cells
+            .iter()
+            .map(|cell| markdown_cell_text(&cell.content))
+            .collect(),
+    )];
+    let mut delimiters: Vec<String> = header
+        .cells
+        .iter()
+        .map(|cell| cell.delimiter.to_string())
+        .collect();
+    delimiters.resize(columns, String::from("---"));
+    lines.push(render(delimiters));
+    lines.extend(body.iter().map(|row| {
+        render(
+            row.cells
+                .iter()
+                .map(|cell| markdown_cell_text(&cell.content))
+                .collect(),
+        )
+    }));
+
+    Ok(lines)
+}
+
+/// Collect the rows and cells of an HTML table block.
+///
+/// Returns the reason the table cannot be converted on failure.
+fn parse_html_rows(block: &str) -> std::result::Result<Vec<HtmlRow>, String> {
+    if !block.trim_end().to_ascii_lowercase().ends_with("</table>") {
+        return Err(String::from("content follows the closing </table> tag"));
+    }
 
-            lines.push(fixed);
+    let mut rows: Vec<HtmlRow> = Vec::new();
+    let mut row: Option<HtmlRow> = None;
+    let mut cell: Option<HtmlCell> = None;
+    let mut in_thead = false;
+    let mut seen_table = false;
+    let mut rest = block;
+
+    loop {
+        let (text, tag) = if let Some(start) = rest.find('<') {
+            let Some(len) = rest[start..].find('>') else {
+                return Err(String::from("contains an unterminated tag"));
+            };
+            let tag = &rest[start + 1..start + len];
+            let text = &rest[..start];
+            rest = &rest[start + len + 1..];
+            (text, Some(tag))
         } else {
-            // Not a table line
-            if in_table {
-                in_table = false;
-                expected_columns = None;
+            (std::mem::take(&mut rest), None)
+        };
+
+        match cell.as_mut() {
+            Some(cell) => cell.content.push_str(text),
+            None if !text.trim().is_empty() => {
+                return Err(String::from("contains text outside of table cells"));
             }
-            lines.push(line.to_string());
+            None => {}
+        }
+
+        let Some(tag) = tag else { break };
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match name.as_str() {
+            "table" if closing => break,
+            "table" if seen_table => return Err(String::from("contains a nested table")),
+            "table" => seen_table = true,
+            "thead" => in_thead = !closing,
+            "tbody" | "tfoot" | "colgroup" | "col" => {}
+            "caption" => return Err(String::from("has a caption")),
+            "tr" => {
+                finish_html_row(&mut rows, &mut row, &mut cell);
+                if !closing {
+                    row = Some(HtmlRow {
+                        in_thead,
+                        cells: Vec::new(),
+                    });
+                }
+            }
+            "td" | "th" => {
+                finish_html_cell(&mut row, &mut cell);
+                if !closing {
+                    for span in ["colspan", "rowspan"] {
+                        if html_attribute(tag, span).is_some_and(|value| value.trim() != "1") {
+                            return Err(format!("uses {span}"));
+                        }
+                    }
+                    cell = Some(HtmlCell {
+                        is_header: name == "th",
+                        delimiter: html_cell_delimiter(tag),
+                        content: String::new(),
+                    });
+                }
+            }
+            _ if HTML_BLOCK_TAGS.contains(&name.as_str()) => {
+                return Err(format!("contains block-level <{name}> content"));
+            }
+            _ => match cell.as_mut() {
+                // Inline HTML stays as-is inside the cell
+                Some(cell) => {
+                    cell.content.push('<');
+                    cell.content.push_str(tag);
+                    cell.content.push('>');
+                }
+                None if tag.starts_with('!') => {}
+                None => return Err(format!("contains unexpected <{name}> markup")),
+            },
         }
     }
+    finish_html_row(&mut rows, &mut row, &mut cell);
 
-    lines.join("\n")
+    Ok(rows)
+}
+
+/// Close the currently open HTML cell, if any.
+fn finish_html_cell(row: &mut Option<HtmlRow>, cell: &mut Option<HtmlCell>) {
+    if let Some(cell) = cell.take() {
+        // Cells outside an explicit row start an implicit one
+        row.get_or_insert_with(|| HtmlRow {
+            in_thead: false,
+            cells: Vec::new(),
+        })
+        .cells
+        .push(cell);
+    }
+}
+
+/// Close the currently open HTML row (and cell), if any.
+fn finish_html_row(
+    rows: &mut Vec<HtmlRow>,
+    row: &mut Option<HtmlRow>,
+    cell: &mut Option<HtmlCell>,
+) {
+    finish_html_cell(row, cell);
+    if let Some(row) = row.take() {
+        rows.push(row);
+    }
+}
+
+/// Get the Markdown delimiter cell matching an HTML cell's alignment.
+fn html_cell_delimiter(tag: &str) -> &'static str {
+    let align = html_attribute(tag, "align").or_else(|| {
+        html_attribute(tag, "style").and_then(|style| {
+            style
+                .split(';')
+                .filter_map(|decl| decl.split_once(':'))
+                .find(|(property, _)| property.trim() == "text-align")
+                .map(|(_, value)| value.trim().to_string())
+        })
+    });
+
+    match align.as_deref() {
+        Some("left") => ":---",
+        Some("center") => ":---:",
+        Some("right") => "---:",
+        _ => "---",
+    }
+}
+
+/// Get the (lowercased) value of an attribute in an HTML start tag.
+fn html_attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search = 0;
+
+    while let Some(pos) = lower[search..].find(name) {
+        let start = search + pos;
+        search = start + name.len();
+
+        if !lower[..start].ends_with(|c: char| c.is_whitespace()) {
+            continue;
+        }
+        let Some(value) = lower[search..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+
+        let value = value.trim_start();
+        for quote in ['"', '\''] {
+            if let Some(quoted) = value.strip_prefix(quote) {
+                return quoted.split(quote).next().map(ToString::to_string);
+            }
+        }
+        return value
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .map(ToString::to_string);
+    }
+
+    None
+}
+
+/// Turn the inner HTML of a cell into single-line Markdown cell text.
+fn markdown_cell_text(content: &str) -> String {
+    let collapsed = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut text = String::with_capacity(collapsed.len());
+    let mut escaped = false;
+
+    for ch in collapsed.chars() {
+        if ch == '|' && !escaped {
+            text.push('\\');
+        }
+        text.push(ch);
+        escaped = ch == '\\' && !escaped;
+    }
+
+    text
 }
 
 #[cfg(test)]
@@ -276,25 +766,58 @@ mod tests {
 
     #[test]
     fn test_fix_headings_no_space() {
-        assert_eq!(fix_headings("#NoSpace"), "# NoSpace");
-        assert_eq!(fix_headings("##Another"), "## Another");
+        assert_eq!(
+            fix_headings("#NoSpace", &HeadingConfig::default()),
+            "# NoSpace"
+        );
+        assert_eq!(
+            fix_headings("##Another", &HeadingConfig::default()),
+            "## Another"
+        );
     }
 
     #[test]
     fn test_fix_headings_trailing_hashes() {
-        assert_eq!(fix_headings("####Trailing####"), "#### Trailing");
-        assert_eq!(fix_headings("# Title #"), "# Title");
+        assert_eq!(
+            fix_headings("####Trailing####", &HeadingConfig::default()),
+            "#### Trailing"
+        );
+        assert_eq!(
+            fix_headings("# Title #", &HeadingConfig::default()),
+            "# Title"
+        );
+    }
+
+    #[test]
+    fn test_fix_headings_closing_hashes() {
+        let config = HeadingConfig {
+            closing_hashes: ClosingHashes::Preserve,
+            ..Default::default()
+        };
+        assert_eq!(fix_headings("# C#", &HeadingConfig::default()), "# C#");
+        assert_eq!(fix_headings("## Title #", &config), "## Title ##");
+        assert_eq!(fix_headings("##Title##", &config), "## Title ##");
+        assert_eq!(fix_headings("## Title", &config), "## Title");
     }
 
     #[test]
     fn test_fix_headings_too_many_spaces() {
-        assert_eq!(fix_headings("###  TooMany"), "### TooMany");
+        assert_eq!(
+            fix_headings("###  TooMany", &HeadingConfig::default()),
+            "### TooMany"
+        );
     }
 
     #[test]
     fn test_fix_headings_preserve_valid() {
-        assert_eq!(fix_headings("# Valid Heading"), "# Valid Heading");
-        assert_eq!(fix_headings("## Another Valid"), "## Another Valid");
+        assert_eq!(
+            fix_headings("# Valid Heading", &HeadingConfig::default()),
+            "# Valid Heading"
+        );
+        assert_eq!(
+            fix_headings("## Another Valid", &HeadingConfig::default()),
+            "## Another Valid"
+        );
     }
 
     #[test]
@@ -313,23 +836,97 @@ mod tests {
     #[test]
     fn test_fix_table_pipes() {
         let mut diagnostics = Diagnostics::new();
-        assert_eq!(fix_table_pipes("Name|Age", &mut diagnostics), "|Name|Age|");
-        assert_eq!(fix_table_pipes("|Name|Age", &mut diagnostics), "|Name|Age|");
-        assert_eq!(fix_table_pipes("Name|Age|", &mut diagnostics), "|Name|Age|");
+        let config = TableConfig::default();
+        assert_eq!(
+            fix_table_pipes("Name|Age", &config, &mut diagnostics),
+            "|Name|Age|"
+        );
+        assert_eq!(
+            fix_table_pipes("|Name|Age", &config, &mut diagnostics),
+            "|Name|Age|"
+        );
+        assert_eq!(
+            fix_table_pipes("Name|Age|", &config, &mut diagnostics),
+            "|Name|Age|"
+        );
+    }
+
+    #[test]
+    fn test_repair_missing_delimiter_row() {
+        let mut diagnostics = Diagnostics::new();
+        let input = "|Name|Age|\n|Alice|30|";
+        let result = fix_table_pipes(input, &TableConfig::default(), &mut diagnostics);
+
+        assert_eq!(result, "|Name|Age|\n|---|---|\n|Alice|30|");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics.messages()[0].kind,
+            DiagnosticKind::MalformedTable
+        );
+    }
+
+    #[test]
+    fn test_repair_skips_prose() {
+        let mut diagnostics = Diagnostics::new();
+        let config = TableConfig::default();
+        let input = "Run a | b to pipe,\nthen c | d again.";
+        assert_eq!(fix_table_pipes(input, &config, &mut diagnostics), input);
+
+        let input = "Use `a | b` here\nand `c | d` there";
+        assert_eq!(fix_table_pipes(input, &config, &mut diagnostics), input);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_repair_ragged_rows_pad() {
+        let mut diagnostics = Diagnostics::new();
+        let input = "|A|B|\n|:-|-|\n|1|\n|1|2|3|";
+        let result = fix_table_pipes(input, &TableConfig::default(), &mut diagnostics);
+
+        assert_eq!(result, "|A|B||\n|:-|-|---|\n|1|||\n|1|2|3|");
+        assert_eq!(diagnostics.len(), 3);
+    }
+
+    #[test]
+    fn test_repair_ragged_rows_merge() {
+        let mut diagnostics = Diagnostics::new();
+        let config = TableConfig {
+            ragged_rows: RaggedRows::Merge,
+            ..Default::default()
+        };
+        let input = "|A|B|\n|---|---|---|\n|1|\n|1|2|3|";
+        let result = fix_table_pipes(input, &config, &mut diagnostics);
+
+        assert_eq!(result, "|A|B|\n|---|---|\n|1||\n|1|2 \\| 3|");
+        assert_eq!(diagnostics.len(), 3);
+    }
+
+    #[test]
+    fn test_repair_ragged_rows_preserve() {
+        let mut diagnostics = Diagnostics::new();
+        let config = TableConfig {
+            ragged_rows: RaggedRows::Preserve,
+            ..Default::default()
+        };
+        let input = "|A|B|\n|---|---|\n|1|";
+        let result = fix_table_pipes(input, &config, &mut diagnostics);
+
+        assert_eq!(result, input);
+        assert_eq!(diagnostics.by_severity(Severity::Warning).len(), 1);
     }
 
     #[test]
     fn test_preprocess_combined() {
         let input = "#NoSpace\n-Item\nName|Age";
         let expected = "# NoSpace\n- Item\n|Name|Age|";
-        let (result, _diagnostics) = preprocess(input);
+        let (result, _diagnostics) = preprocess(input, &Config::default());
         assert_eq!(result, expected);
     }
 
     #[test]
     fn test_bold_not_list() {
         let input = "**Table of Contents:**\n- Item 1";
-        let (result, _diag) = preprocess(input);
+        let (result, _diag) = preprocess(input, &Config::default());
         eprintln!("INPUT:\n{input}");
         eprintln!("\nOUTPUT:\n{result}");
 
@@ -337,4 +934,65 @@ mod tests {
         assert!(result.contains("**Table of Contents:**"));
         assert!(!result.contains("* *Table"));
     }
+
+    #[test]
+    fn test_emphasis_not_list() {
+        assert_eq!(fix_list_markers("*Note:* read this"), "*Note:* read this");
+        assert_eq!(fix_list_markers("*Item"), "* Item");
+    }
+
+    #[test]
+    fn test_convert_html_table() {
+        let mut diagnostics = Diagnostics::new();
+        let input = "<table>\n<tr><th>Name</th><th align=\"right\">Age</th></tr>\n<tr><td>Alice</td><td>30</td></tr>\n<tr><td><b>Bob</b> | Jr</td></tr>\n</table>";
+        let result = convert_html_tables(input, &mut diagnostics);
+
+        assert_eq!(
+            result,
+            "| Name | Age |\n| --- | ---: |\n| Alice | 30 |\n| <b>Bob</b> \\| Jr |  |"
+        );
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_convert_html_table_thead() {
+        let mut diagnostics = Diagnostics::new();
+        let input = "<table><thead><tr><td>A</td></tr></thead><tbody><tr><td colspan=\"1\">x</td></tr></tbody></table>";
+        let result = convert_html_tables(input, &mut diagnostics);
+
+        assert_eq!(result, "| A |\n| --- |\n| x |");
+    }
+
+    #[test]
+    fn test_convert_html_table_unsupported() {
+        let inputs = [
+            (
+                "<table><tr><th>A</th></tr><tr><td colspan=\"2\">x</td></tr></table>",
+                "colspan",
+            ),
+            (
+                "<table><tr><th>A</th></tr><tr><td><p>x</p></td></tr></table>",
+                "<p>",
+            ),
+            ("<table><tr><td>A</td></tr></table>", "no header row"),
+        ];
+
+        for (input, reason) in inputs {
+            let mut diagnostics = Diagnostics::new();
+            let result = convert_html_tables(input, &mut diagnostics);
+
+            assert_eq!(result, input);
+            assert_eq!(diagnostics.len(), 1);
+            assert!(diagnostics.messages()[0].message.contains(reason));
+        }
+    }
+
+    #[test]
+    fn test_convert_html_table_disabled_by_default() {
+        let input = "<table><tr><th>A</th></tr></table>";
+        let (result, diagnostics) = preprocess(input, &Config::default());
+
+        assert_eq!(result, input);
+        assert!(diagnostics.is_empty());
+    }
 }
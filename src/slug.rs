@@ -0,0 +1,231 @@
+//! Heading anchor slugs.
+//!
+//! Derives the `#anchor` that renderers generate for a heading, so that
+//! generated and checked links point at the right place.
+
+use std::collections::{HashMap, HashSet};
+
+use pulldown_cmark::{Event, Tag, TagEnd};
+
+use crate::config::SlugStyle;
+use crate::formatter::{parse_markdown, split_front_matter};
+
+/// Generate a GitHub-style slug for heading text.
+///
+/// Lowercases the text, drops punctuation and turns spaces into hyphens:
+/// `"Hello, World!"` becomes `"hello-world"`.
+pub fn github(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Generate a GitLab-style slug for heading text.
+///
+/// Same as [`github`], but runs of hyphens collapse into one:
+/// `"A - B"` becomes `"a-b"`.
+pub fn gitlab(text: &str) -> String {
+    let mut slug = String::new();
+    for c in github(text).chars() {
+        if !(c == '-' && slug.ends_with('-')) {
+            slug.push(c);
+        }
+    }
+    slug
+}
+
+/// ASCII letter each of U+00C0 to U+017F decomposes to under NFKD, or a
+/// space when the decomposition has no ASCII letter (`Æ`, `ß`, `Ł`, ...).
+const LATIN_BASE: &str = concat!(
+    "AAAAAA CEEEEIIII NOOOOO  UUUUY  aaaaaa ceeeeiiii nooooo  uuuuy y",
+    "AaAaAaCcCcCcCcDd  EeEeEeEeEeGgGgGgGgHh  IiIiIiIiI   JjKk LlLlLlL",
+    "l  NnNnNnn  OoOoOo  RrRrRrSsSsSsSsTtTt  UuUuUuUuUuUuWwYyYZzZzZzs",
+);
+
+/// Push the ASCII letters `c` keeps after NFKD normalization, dropping
+/// diacritics the way `unicodedata.normalize("NFKD", ...)` followed by an
+/// ASCII encode does.
+fn push_ascii(c: char, out: &mut String) {
+    let folded = match c {
+        c if c.is_ascii() => {
+            out.push(c);
+            return;
+        }
+        '\u{0132}' => "IJ",
+        '\u{0133}' => "ij",
+        '\u{fb00}' => "ff",
+        '\u{fb01}' => "fi",
+        '\u{fb02}' => "fl",
+        '\u{fb03}' => "ffi",
+        '\u{fb04}' => "ffl",
+        '\u{fb05}' | '\u{fb06}' => "st",
+        '\u{00c0}'..='\u{017f}' => {
+            let base = LATIN_BASE.as_bytes()[c as usize - 0xc0];
+            if base != b' ' {
+                out.push(char::from(base));
+            }
+            return;
+        }
+        _ => return,
+    };
+    out.push_str(folded);
+}
+
+/// Generate an `MkDocs`-style slug for heading text.
+///
+/// Normalizes the text with NFKD and keeps ASCII word characters only, so
+/// accents are dropped from their letters, then turns each run of spaces and
+/// hyphens into a single hyphen: `"Café - Menu"` becomes `"cafe-menu"`.
+pub fn mkdocs(text: &str) -> String {
+    let mut ascii = String::new();
+    for c in text.chars() {
+        push_ascii(c, &mut ascii);
+    }
+    let text: String = ascii
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .collect();
+
+    text.trim()
+        .to_lowercase()
+        .split([' ', '-'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Generate a slug for heading text with the given algorithm.
+pub fn slugify(text: &str, style: SlugStyle) -> String {
+    match style {
+        SlugStyle::Github => github(text),
+        SlugStyle::Gitlab => gitlab(text),
+        SlugStyle::Mkdocs => mkdocs(text),
+    }
+}
+
+/// Generates unique slugs within a single document.
+///
+/// Repeated slugs get a numeric suffix (`intro`, `intro-1`, `intro-2`), the
+/// same way GitHub disambiguates duplicate headings. `MkDocs` uses an
+/// underscore instead (`intro_1`).
+#[derive(Debug, Default)]
+pub struct Slugger {
+    style: SlugStyle,
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    /// Create a new slugger using the given slug algorithm.
+    pub fn with_style(style: SlugStyle) -> Self {
+        Self {
+            style,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Generate the unique slug for the next heading with this text.
+    pub fn slug(&mut self, text: &str) -> String {
+        self.unique(&slugify(text, self.style))
+    }
+
+    /// Make `base` unique among the slugs seen so far and record it.
+    pub fn unique(&mut self, base: &str) -> String {
+        let separator = if self.style == SlugStyle::Mkdocs {
+            '_'
+        } else {
+            '-'
+        };
+        let mut slug = base.to_string();
+        while self.seen.contains_key(&slug) {
+            let count = self.seen.entry(base.to_string()).or_insert(0);
+            *count += 1;
+            slug = format!("{base}{separator}{count}");
+        }
+        self.seen.insert(slug.clone(), 0);
+        slug
+    }
+}
+
+/// Collect the anchors of every heading in a document.
+///
+/// Headings with an explicit `{#id}` use it, the others get a slug of their
+/// text, disambiguated in document order.
+pub fn document_anchors(content: &str, style: SlugStyle) -> HashSet<String> {
+    let mut slugger = Slugger::with_style(style);
+    let mut anchors = HashSet::new();
+    let mut heading: Option<(Option<String>, String)> = None;
+
+    let (_, body) = split_front_matter(content);
+    for (event, _) in parse_markdown(body) {
+        match event {
+            Event::Start(Tag::Heading { id, .. }) => {
+                heading = Some((id.map(|id| id.to_string()), String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((id, text)) = heading.take() {
+                    anchors.insert(match id {
+                        Some(id) => slugger.unique(&id),
+                        None => slugger.slug(&text),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    anchors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_slug() {
+        assert_eq!(github("Hello, World!"), "hello-world");
+        assert_eq!(github("Use `format_file()` now"), "use-format_file-now");
+        assert_eq!(github("Über uns 🎉"), "über-uns-");
+    }
+
+    #[test]
+    fn test_slugger_duplicates() {
+        let mut slugger = Slugger::default();
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro-1");
+        assert_eq!(slugger.slug("Intro 1"), "intro-1-1");
+        assert_eq!(slugger.slug("Intro"), "intro-2");
+    }
+
+    #[test]
+    fn test_slug_styles() {
+        assert_eq!(gitlab("A - B"), "a-b");
+        assert_eq!(github("A - B"), "a---b");
+        assert_eq!(mkdocs("Café - Menu!"), "cafe-menu");
+        assert_eq!(mkdocs("Über Straße, Łódź"), "uber-strae-odz");
+        assert_eq!(mkdocs("Ĳssel ﬁnal"), "ijssel-final");
+        assert_eq!(mkdocs("  Use `format_file()` now "), "use-format_file-now");
+
+        let mut slugger = Slugger::with_style(SlugStyle::Mkdocs);
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro_1");
+    }
+
+    #[test]
+    fn test_document_anchors() {
+        let anchors = document_anchors(
+            "# Intro\n\n## Intro\n\n## Setup {#install}\n",
+            SlugStyle::Github,
+        );
+        let mut anchors: Vec<_> = anchors.into_iter().collect();
+        anchors.sort();
+        assert_eq!(anchors, ["install", "intro", "intro-1"]);
+    }
+}
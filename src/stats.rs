@@ -0,0 +1,53 @@
+//! Document statistics.
+//!
+//! Counts derived from the parsed document, such as task list progress.
+
+use pulldown_cmark::Event;
+
+use crate::formatter::parse_markdown;
+
+/// Task list progress of a document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Number of checked tasks (`- [x]`).
+    pub done: usize,
+
+    /// Number of tasks, checked or not.
+    pub total: usize,
+}
+
+/// Count the checked and total task list items in markdown content.
+///
+/// # Examples
+///
+/// ```
+/// use beautiful_md::stats::task_stats;
+///
+/// let stats = task_stats("- [x] Write docs\n- [ ] Release\n- Note");
+/// assert_eq!((stats.done, stats.total), (1, 2));
+/// ```
+#[must_use]
+pub fn task_stats(content: &str) -> TaskStats {
+    parse_markdown(content)
+        .iter()
+        .fold(TaskStats::default(), |mut stats, (event, _)| {
+            if let Event::TaskListMarker(checked) = event {
+                stats.total += 1;
+                if *checked {
+                    stats.done += 1;
+                }
+            }
+            stats
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_stats_nested_and_code() {
+        let content = "- [x] A\n  - [X] B\n  - [ ] C\n\n```\n- [ ] not a task\n```\n";
+        assert_eq!(task_stats(content), TaskStats { done: 2, total: 3 });
+    }
+}